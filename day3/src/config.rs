@@ -0,0 +1,202 @@
+use std::collections::HashSet;
+
+use crate::row::to_digit;
+
+/// Describes how a schematic should be read: which character is blank space, which characters are
+/// symbols, which symbols are gears and how a gear's parts are combined into a ratio.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchematicConfig {
+    /// the character that fills empty space in the schematic
+    pub blank: char,
+
+    /// the characters that count as symbols
+    pub symbols: SymbolSet,
+
+    /// the symbols that can act as gears
+    pub gear_symbols: HashSet<char>,
+
+    /// decides which gears count and how their ratio is computed
+    pub gear_rule: GearRule,
+
+    /// decides which cells around a number are its neighbours
+    pub adjacency: Adjacency,
+
+    /// when set, physical lines are joined into logical rows of this many graphemes before
+    /// scanning, for schematics whose long rows have been hard-wrapped
+    pub logical_width: Option<usize>,
+}
+
+impl SchematicConfig {
+    /// returns true if the grapheme is a symbol. Non-ASCII graphemes, such as box-drawing
+    /// characters, are always symbols unless they are blank.
+    pub fn is_symbol(&self, grapheme: &str) -> bool {
+        let c = single_char(grapheme);
+
+        if to_digit(grapheme).is_some() || c == Some(self.blank) {
+            return false;
+        }
+
+        if !grapheme.is_ascii() {
+            return true;
+        }
+
+        match (&self.symbols, c) {
+            (SymbolSet::AnyOther, _) => true,
+            (SymbolSet::Only(symbols), Some(c)) => symbols.contains(&c),
+            (SymbolSet::Only(_), None) => false,
+        }
+    }
+
+    pub fn is_gear(&self, grapheme: &str) -> bool {
+        single_char(grapheme).is_some_and(|c| self.gear_symbols.contains(&c))
+    }
+}
+
+/// returns the char if the grapheme is made of exactly one
+fn single_char(grapheme: &str) -> Option<char> {
+    let mut chars = grapheme.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(c),
+        _ => None,
+    }
+}
+
+/// The default configuration is the one described by the original puzzle: '.' is blank, every
+/// other non-digit is a symbol, and a '*' next to exactly two part numbers is a gear whose ratio
+/// is the product of those part numbers.
+impl Default for SchematicConfig {
+    fn default() -> Self {
+        Self {
+            blank: '.',
+            symbols: SymbolSet::AnyOther,
+            gear_symbols: HashSet::from(['*']),
+            gear_rule: GearRule::default(),
+            adjacency: Adjacency::EightNeighbour,
+            logical_width: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SymbolSet {
+    /// every character that is not a digit or blank is a symbol
+    AnyOther,
+
+    /// only the listed characters are symbols
+    Only(HashSet<char>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GearRule {
+    /// how many adjacent part numbers a gear needs
+    pub count: PartCount,
+
+    /// how the adjacent part numbers are combined into the gear ratio
+    pub aggregate: Aggregate,
+}
+
+impl GearRule {
+    pub fn accepts(&self, part_numbers_count: u32) -> bool {
+        match self.count {
+            PartCount::Exactly(n) => part_numbers_count == n,
+            PartCount::AtLeast(n) => part_numbers_count >= n,
+        }
+    }
+}
+
+impl Default for GearRule {
+    fn default() -> Self {
+        Self {
+            count: PartCount::Exactly(2),
+            aggregate: Aggregate::Product,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PartCount {
+    Exactly(u32),
+    AtLeast(u32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Aggregate {
+    Sum,
+    Product,
+    Max,
+}
+
+impl Aggregate {
+    /// the value of a gear before any part numbers have been combined into it
+    pub fn identity(&self) -> u64 {
+        match self {
+            Aggregate::Sum => 0,
+            Aggregate::Product => 1,
+            Aggregate::Max => 0,
+        }
+    }
+
+    /// combines a part number into a gear's value, or returns None if the result is out of range
+    pub fn combine(&self, acc: u64, part_number: u64) -> Option<u64> {
+        match self {
+            Aggregate::Sum => acc.checked_add(part_number),
+            Aggregate::Product => acc.checked_mul(part_number),
+            Aggregate::Max => Some(acc.max(part_number)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Adjacency {
+    /// the 8 cells around each digit, clipped at the edges of the schematic
+    EightNeighbour,
+
+    /// the cells above, below, left and right of each digit
+    Orthogonal,
+
+    /// the 4 cells diagonal to each digit
+    Diagonal,
+
+    /// the 8 cells around each digit, wrapping around both edges of the schematic
+    Toroidal,
+
+    /// every cell within the given Manhattan distance of a digit
+    Manhattan(usize),
+}
+
+impl Adjacency {
+    /// how many rows above and below a number can hold its neighbours
+    pub fn radius(&self) -> usize {
+        match self {
+            Adjacency::Manhattan(k) => *k,
+            _ => 1,
+        }
+    }
+
+    pub fn wraps(&self) -> bool {
+        *self == Adjacency::Toroidal
+    }
+
+    /// the (row, column) offsets of the neighbours of a single digit
+    pub fn offsets(&self) -> Vec<(isize, isize)> {
+        let radius = self.radius() as isize;
+        let mut offsets = Vec::new();
+
+        for row in -radius..=radius {
+            for column in -radius..=radius {
+                let is_neighbour = match self {
+                    Adjacency::EightNeighbour | Adjacency::Toroidal => true,
+                    Adjacency::Orthogonal => row == 0 || column == 0,
+                    Adjacency::Diagonal => row != 0 && column != 0,
+                    Adjacency::Manhattan(k) => row.abs() + column.abs() <= *k as isize,
+                };
+
+                if is_neighbour && (row, column) != (0, 0) {
+                    offsets.push((row, column));
+                }
+            }
+        }
+
+        offsets
+    }
+}
@@ -0,0 +1,93 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+use crate::wrap::LineMap;
+use crate::Coordinate;
+
+#[derive(Debug)]
+pub enum SchematicError {
+    Io(io::Error),
+
+    /// a number in the schematic doesn't fit in a u64
+    NumberOutOfRange {
+        coordinate: Coordinate,
+    },
+
+    /// the ratio of the gear doesn't fit in a u64
+    GearRatioOutOfRange {
+        coordinate: Coordinate,
+    },
+
+    /// the sum of the part numbers or gear ratios doesn't fit in a u64
+    SumOutOfRange,
+
+    /// the physical lines of a hard-wrapped schematic don't join into rows of the logical width,
+    /// because the line runs past the end of a row or the last row is cut short. Lines start
+    /// from 0.
+    MisalignedRow {
+        line: usize,
+        width: usize,
+        logical_width: usize,
+    },
+}
+
+impl SchematicError {
+    /// maps the coordinate of the error from logical rows back to the wrapped schematic
+    pub(crate) fn into_physical(self, line_map: &LineMap) -> Self {
+        match self {
+            SchematicError::NumberOutOfRange { coordinate } => SchematicError::NumberOutOfRange {
+                coordinate: line_map.to_physical(&coordinate),
+            },
+            SchematicError::GearRatioOutOfRange { coordinate } => {
+                SchematicError::GearRatioOutOfRange {
+                    coordinate: line_map.to_physical(&coordinate),
+                }
+            }
+            e => e,
+        }
+    }
+}
+
+impl fmt::Display for SchematicError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchematicError::Io(e) => write!(f, "failed to read schematic: {}", e),
+            SchematicError::NumberOutOfRange { coordinate } => write!(
+                f,
+                "number at row {}, column {} is out of range",
+                coordinate.row, coordinate.column
+            ),
+            SchematicError::GearRatioOutOfRange { coordinate } => write!(
+                f,
+                "ratio of gear at row {}, column {} is out of range",
+                coordinate.row, coordinate.column
+            ),
+            SchematicError::SumOutOfRange => write!(f, "sum is out of range"),
+            SchematicError::MisalignedRow {
+                line,
+                width,
+                logical_width,
+            } => write!(
+                f,
+                "line {} ends a row {} wide, but rows are {} wide",
+                line, width, logical_width
+            ),
+        }
+    }
+}
+
+impl Error for SchematicError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SchematicError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for SchematicError {
+    fn from(e: io::Error) -> Self {
+        SchematicError::Io(e)
+    }
+}
@@ -0,0 +1,237 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::ops::Range;
+
+use crate::{Coordinate, NumberInfo, SchematicConfig, SchematicError, Symbol};
+
+/// An undirected graph with a node for every number and every symbol in a schematic, and an edge
+/// wherever a number is adjacent to a symbol.
+#[derive(Debug, Default)]
+pub struct SchematicGraph {
+    numbers: Vec<NumberInfo>,
+    symbols: Vec<Symbol>,
+
+    /// looks up a symbol's index by its coordinate
+    symbol_index: HashMap<Coordinate, usize>,
+
+    /// for each number, the indexes of the symbols it touches
+    number_edges: Vec<Vec<usize>>,
+
+    /// for each symbol, the indexes of the numbers it touches
+    symbol_edges: Vec<Vec<usize>>,
+}
+
+/// A set of numbers and symbols that are connected to each other through adjacency
+#[derive(Debug, PartialEq)]
+pub struct Component<'a> {
+    pub numbers: Vec<&'a NumberInfo>,
+    pub symbols: Vec<&'a Symbol>,
+}
+
+impl SchematicGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// adds a number along with the symbols surrounding it
+    pub(crate) fn add_number(&mut self, number: NumberInfo, surrounding_symbols: Vec<Symbol>) {
+        let number_idx = self.numbers.len();
+        self.numbers.push(number);
+        self.number_edges.push(Vec::new());
+
+        for symbol in surrounding_symbols {
+            let symbol_idx = self.add_symbol(symbol);
+            self.number_edges[number_idx].push(symbol_idx);
+            self.symbol_edges[symbol_idx].push(number_idx);
+        }
+    }
+
+    /// adds a symbol if it hasn't been seen yet, returns its index
+    pub(crate) fn add_symbol(&mut self, symbol: Symbol) -> usize {
+        if let Some(&idx) = self.symbol_index.get(&symbol.coordinate) {
+            return idx;
+        }
+
+        let idx = self.symbols.len();
+        self.symbol_index.insert(symbol.coordinate.clone(), idx);
+        self.symbols.push(symbol);
+        self.symbol_edges.push(Vec::new());
+        idx
+    }
+
+    /// moves every number and symbol to a new coordinate and byte span
+    pub(crate) fn map_positions(
+        &mut self,
+        f: impl Fn(&Coordinate, &Range<usize>) -> (Coordinate, Range<usize>),
+    ) {
+        for number in self.numbers.iter_mut() {
+            (number.coordinate, number.byte_span) = f(&number.coordinate, &number.byte_span);
+        }
+
+        self.symbol_index.clear();
+        for (idx, symbol) in self.symbols.iter_mut().enumerate() {
+            (symbol.coordinate, symbol.byte_span) = f(&symbol.coordinate, &symbol.byte_span);
+            self.symbol_index.insert(symbol.coordinate.clone(), idx);
+        }
+    }
+
+    pub fn numbers(&self) -> &[NumberInfo] {
+        &self.numbers
+    }
+
+    pub fn symbols(&self) -> &[Symbol] {
+        &self.symbols
+    }
+
+    /// returns the numbers that touch the symbol at coordinate
+    pub fn numbers_touching(&self, coordinate: &Coordinate) -> Vec<&NumberInfo> {
+        match self.symbol_index.get(coordinate) {
+            Some(&idx) => self.symbol_edges[idx]
+                .iter()
+                .map(|&n| &self.numbers[n])
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// returns the symbols that touch the given number
+    pub fn symbols_touching(&self, number: &NumberInfo) -> Vec<&Symbol> {
+        match self.numbers.iter().position(|n| n == number) {
+            Some(idx) => self.number_edges[idx]
+                .iter()
+                .map(|&s| &self.symbols[s])
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// iterates over every number along with whether it is a part number, i.e. touches a symbol
+    pub fn classified_numbers(&self) -> impl Iterator<Item = (&NumberInfo, bool)> {
+        self.numbers
+            .iter()
+            .zip(self.number_edges.iter())
+            .map(|(n, edges)| (n, !edges.is_empty()))
+    }
+
+    /// returns the gear symbols accepted by config's gear rule, along with their gear ratio
+    pub fn gears(&self, config: &SchematicConfig) -> Result<Vec<(&Symbol, u64)>, SchematicError> {
+        let aggregate = config.gear_rule.aggregate;
+
+        self.symbols
+            .iter()
+            .zip(self.symbol_edges.iter())
+            .filter(|(symbol, edges)| {
+                let count = u32::try_from(edges.len()).unwrap_or(u32::MAX);
+                config.is_gear(&symbol.symbol) && config.gear_rule.accepts(count)
+            })
+            .map(|(symbol, edges)| {
+                let ratio = edges
+                    .iter()
+                    .map(|&n| self.numbers[n].number)
+                    .try_fold(aggregate.identity(), |acc, n| aggregate.combine(acc, n))
+                    .ok_or_else(|| SchematicError::GearRatioOutOfRange {
+                        coordinate: symbol.coordinate.clone(),
+                    })?;
+                Ok((symbol, ratio))
+            })
+            .collect()
+    }
+
+    /// returns the numbers that aren't adjacent to any symbol, i.e. the numbers that aren't parts
+    pub fn isolated_numbers(&self) -> Vec<&NumberInfo> {
+        self.numbers
+            .iter()
+            .zip(self.number_edges.iter())
+            .filter(|(_, edges)| edges.is_empty())
+            .map(|(n, _)| n)
+            .collect()
+    }
+
+    /// groups numbers and symbols that can reach each other through adjacency
+    pub fn connected_components(&self) -> Vec<Component<'_>> {
+        let mut visited_numbers = vec![false; self.numbers.len()];
+        let mut visited_symbols = vec![false; self.symbols.len()];
+        let mut components = Vec::new();
+
+        for start in 0..self.numbers.len() {
+            if visited_numbers[start] {
+                continue;
+            }
+
+            let mut component = Component {
+                numbers: Vec::new(),
+                symbols: Vec::new(),
+            };
+
+            // depth first search, alternating between numbers and symbols
+            visited_numbers[start] = true;
+            let mut stack = vec![start];
+
+            while let Some(number_idx) = stack.pop() {
+                component.numbers.push(&self.numbers[number_idx]);
+
+                for &symbol_idx in &self.number_edges[number_idx] {
+                    if visited_symbols[symbol_idx] {
+                        continue;
+                    }
+                    visited_symbols[symbol_idx] = true;
+                    component.symbols.push(&self.symbols[symbol_idx]);
+
+                    for &next in &self.symbol_edges[symbol_idx] {
+                        if !visited_numbers[next] {
+                            visited_numbers[next] = true;
+                            stack.push(next);
+                        }
+                    }
+                }
+            }
+
+            components.push(component);
+        }
+
+        // symbols with no numbers around them are components of their own
+        for (idx, symbol) in self.symbols.iter().enumerate() {
+            if !visited_symbols[idx] {
+                components.push(Component {
+                    numbers: Vec::new(),
+                    symbols: vec![symbol],
+                });
+            }
+        }
+
+        components
+    }
+
+    /// renders the graph in graphviz DOT format
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("graph schematic {\n");
+
+        for (idx, n) in self.numbers.iter().enumerate() {
+            let _ = writeln!(
+                dot,
+                "    n{} [label=\"{} ({}, {})\", shape=box];",
+                idx, n.number, n.coordinate.row, n.coordinate.column
+            );
+        }
+
+        for (idx, s) in self.symbols.iter().enumerate() {
+            let _ = writeln!(
+                dot,
+                "    s{} [label=\"{} ({}, {})\", shape=circle];",
+                idx,
+                s.symbol.escape_default(),
+                s.coordinate.row,
+                s.coordinate.column
+            );
+        }
+
+        for (number_idx, edges) in self.number_edges.iter().enumerate() {
+            for symbol_idx in edges {
+                let _ = writeln!(dot, "    n{} -- s{};", number_idx, symbol_idx);
+            }
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+}
@@ -1,12 +1,31 @@
+use row::{to_digit, Row};
 use std::cmp::min;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, VecDeque};
 use std::fs::File;
-use std::io;
 use std::io::{BufRead, BufReader};
+use std::ops::Range;
+use wrap::{LineMap, LogicalLines};
+
+mod config;
+mod error;
+mod graph;
+mod parallel;
+mod render;
+mod row;
+mod wrap;
+
+pub use config::{Adjacency, Aggregate, GearRule, PartCount, SchematicConfig, SymbolSet};
+pub use error::SchematicError;
+pub use graph::{Component, SchematicGraph};
+pub use parallel::{process_parallel, SchematicSums};
+pub use render::RenderMode;
+
+pub fn pt1() -> Result<u64, SchematicError> {
+    pt1_with_config("input", &SchematicConfig::default())
+}
 
-pub fn pt1() -> Result<u32, io::Error> {
-    let path = "input";
-
+/// sums the part numbers of the schematic at path, using config to decide what counts as a symbol
+pub fn pt1_with_config(path: &str, config: &SchematicConfig) -> Result<u64, SchematicError> {
     let file = File::open(path)?;
 
     let buffered = BufReader::new(file);
@@ -15,19 +34,23 @@ pub fn pt1() -> Result<u32, io::Error> {
     let mut lines_iter = buffered.lines().filter_map(|line_result| line_result.ok());
 
     // instantiate a sliding window of 3 lines
-    let mut window = Window::new();
+    let mut window = Window::new(config.clone());
 
-    let result = window.process_lines(&mut lines_iter);
+    let result = window.process_lines(&mut lines_iter)?;
 
-    let answer = result.part_numbers.iter().map(|x| x.number).sum::<u32>();
+    let answer = checked_sum(result.part_numbers.iter().map(|x| x.number))?;
 
-    println!("part numbers {:?}", result.part_numbers);
     println!("sum {}", answer);
 
     return Ok(answer);
 }
 
-pub fn pt2(path: &str) -> Result<u32, io::Error> {
+pub fn pt2(path: &str) -> Result<u64, SchematicError> {
+    pt2_with_config(path, &SchematicConfig::default())
+}
+
+/// sums the gear ratios of the schematic at path, using config to decide what counts as a gear
+pub fn pt2_with_config(path: &str, config: &SchematicConfig) -> Result<u64, SchematicError> {
     let file = File::open(path)?;
 
     let buffered = BufReader::new(file);
@@ -36,76 +59,282 @@ pub fn pt2(path: &str) -> Result<u32, io::Error> {
     let mut lines_iter = buffered.lines().filter_map(|line_result| line_result.ok());
 
     // instantiate a sliding window of 3 lines
-    let mut window = Window::new();
+    let mut window = Window::new(config.clone());
 
-    let result = window.process_lines(&mut lines_iter);
+    let result = window.process_lines(&mut lines_iter)?;
 
-    let answer = result.gears.iter().map(|g| g.gear_ratio).sum();
+    let answer = checked_sum(result.gears.iter().map(|g| g.gear_ratio))?;
     println!("sum {}", answer);
 
     return Ok(answer);
 }
 
+/// computes the answers to pt1 and pt2 together, scanning bands of rows on separate threads
+pub fn sums_parallel(
+    path: &str,
+    config: &SchematicConfig,
+    threads: usize,
+) -> Result<SchematicSums, SchematicError> {
+    let file = File::open(path)?;
+
+    let lines: Vec<String> = BufReader::new(file).lines().collect::<Result<_, _>>()?;
+
+    process_parallel(&lines, config, threads)
+}
+
+/// builds the adjacency graph between the numbers and symbols of the schematic at path
+pub fn graph(path: &str, config: &SchematicConfig) -> Result<SchematicGraph, SchematicError> {
+    let file = File::open(path)?;
+
+    let buffered = BufReader::new(file);
+
+    // create an iterator over the lines of the file
+    let mut lines_iter = buffered.lines().map_while(Result::ok);
+
+    let mut window = Window::new(config.clone());
+
+    window.build_graph(&mut lines_iter)
+}
+
+/// reprints the schematic at path, annotated with what the scanner decided about each character
+pub fn render_schematic(
+    path: &str,
+    config: &SchematicConfig,
+    mode: RenderMode,
+) -> Result<String, SchematicError> {
+    let file = File::open(path)?;
+
+    let lines: Vec<String> = BufReader::new(file).lines().collect::<Result<_, _>>()?;
+
+    let mut window = Window::new(config.clone());
+
+    let graph = window.build_graph(&mut lines.iter().cloned())?;
+
+    render::render(&lines, &graph, config, mode)
+}
+
+/// A sliding window over the rows of a schematic. It holds the row being scanned along with the
+/// rows within the adjacency radius above and below it.
 struct Window {
-    previous: Option<Vec<char>>,
-    middle: Option<Vec<char>>,
-    next: Option<Vec<char>>,
-    row: usize,
+    /// the rows in view, rows[0] is row first_row of the schematic
+    rows: VecDeque<Row>,
+
+    /// the index of the first row in view
+    first_row: usize,
+
+    /// the first rows of the schematic, kept until the end of the input when adjacency wraps
+    head: Vec<Row>,
+
+    /// the number of rows in the schematic, known once the input runs out
+    total_rows: Option<usize>,
+
+    config: SchematicConfig,
 }
 
 impl Window {
-    fn new() -> Self {
+    fn new(config: SchematicConfig) -> Self {
         Self {
-            previous: None,
-            middle: None,
-            next: None,
-            row: 0,
+            rows: VecDeque::new(),
+            first_row: 0,
+            head: Vec::new(),
+            total_rows: None,
+            config,
         }
     }
 
-    fn process_lines(&mut self, lines: &mut impl Iterator<Item = String>) -> ProcessingResult {
-        let mut prospective_gears = HashMap::new();
-        let mut part_numbers = Vec::new();
-        // move the sliding window forward one line at a time
-        loop {
-            self.move_forward(lines.next());
-            self.scan_for_part_numbers(&mut prospective_gears, &mut part_numbers);
+    fn process_lines(
+        &mut self,
+        lines: &mut impl Iterator<Item = String>,
+    ) -> Result<ProcessingResult, SchematicError> {
+        match self.config.logical_width {
+            Some(width) => {
+                let mut line_map = LineMap::new();
+                let mut misaligned = None;
+                let result = self.process_rows(
+                    &mut LogicalLines::new(lines, width, &mut line_map)
+                        .map_while(|row| row.map_err(|e| misaligned = Some(e)).ok()),
+                );
+                if let Some(e) = misaligned {
+                    return Err(e);
+                }
 
-            // end when the sliding window becomes empty
-            if self.is_empty() {
-                break;
+                // report part numbers and gears where they appear in the wrapped schematic
+                result
+                    .map(|mut result| {
+                        for num in result.part_numbers.iter_mut() {
+                            num.byte_span =
+                                line_map.byte_span_to_physical(num.coordinate.row, &num.byte_span);
+                            num.coordinate = line_map.to_physical(&num.coordinate);
+                        }
+                        for gear in result.gears.iter_mut() {
+                            gear.coordinate = line_map.to_physical(&gear.coordinate);
+                        }
+                        result
+                    })
+                    .map_err(|e| e.into_physical(&line_map))
             }
+            None => self.process_rows(lines),
         }
+    }
+
+    fn process_rows(
+        &mut self,
+        lines: &mut impl Iterator<Item = String>,
+    ) -> Result<ProcessingResult, SchematicError> {
+        let mut prospective_gears = HashMap::new();
+        let mut part_numbers = Vec::new();
+
+        self.scan(lines, |window, row| {
+            window.scan_for_part_numbers(row, &mut prospective_gears, &mut part_numbers)
+        })?;
 
-        let gears = gears(prospective_gears);
+        let gears = gears(prospective_gears, &self.config.gear_rule)?;
 
-        return ProcessingResult {
+        Ok(ProcessingResult {
             gears,
             part_numbers,
-        };
+        })
     }
 
-    fn move_forward(&mut self, next_line: Option<String>) -> () {
-        self.previous = self.middle.take();
-        self.middle = self.next.take();
-        self.next = next_line.map(|s| s.chars().collect());
-        if self.previous.is_some() {
-            self.row += 1;
+    /// like process_lines, but keeps every number and symbol along with their adjacency
+    fn build_graph(
+        &mut self,
+        lines: &mut impl Iterator<Item = String>,
+    ) -> Result<SchematicGraph, SchematicError> {
+        match self.config.logical_width {
+            Some(width) => {
+                let mut line_map = LineMap::new();
+                let mut misaligned = None;
+                let graph = self.build_graph_from_rows(
+                    &mut LogicalLines::new(lines, width, &mut line_map)
+                        .map_while(|row| row.map_err(|e| misaligned = Some(e)).ok()),
+                );
+                if let Some(e) = misaligned {
+                    return Err(e);
+                }
+
+                graph
+                    .map(|mut graph| {
+                        graph.map_positions(|coordinate, byte_span| {
+                            (
+                                line_map.to_physical(coordinate),
+                                line_map.byte_span_to_physical(coordinate.row, byte_span),
+                            )
+                        });
+                        graph
+                    })
+                    .map_err(|e| e.into_physical(&line_map))
+            }
+            None => self.build_graph_from_rows(lines),
         }
     }
 
-    fn is_empty(&self) -> bool {
-        self.previous.is_none() && self.middle.is_none() && self.next.is_none()
+    fn build_graph_from_rows(
+        &mut self,
+        lines: &mut impl Iterator<Item = String>,
+    ) -> Result<SchematicGraph, SchematicError> {
+        let mut graph = SchematicGraph::new();
+
+        self.scan(lines, |window, row| {
+            if let Some((_, line)) = window.row(row as isize) {
+                // add symbols first so isolated symbols are kept too
+                for symbol in scan_for_symbols(line, row, &window.config) {
+                    graph.add_symbol(symbol);
+                }
+
+                for num in scan_for_numbers(line, row)? {
+                    let surrounding_symbols = window.surrounding_symbols(&num);
+                    graph.add_number(num, surrounding_symbols);
+                }
+            }
+            Ok(())
+        })?;
+
+        Ok(graph)
     }
 
-    /// scans the middle line for part numbers, scans for potential gears around part numbers
-    fn scan_for_part_numbers(
+    /// moves the window forward one line at a time, calling visit with the index of each row once
+    /// every row within the adjacency radius is in view. When adjacency wraps, the first rows are
+    /// visited last, since their neighbours above are the last rows of the input. Stops at the
+    /// first error returned by visit.
+    fn scan(
         &mut self,
+        lines: &mut impl Iterator<Item = String>,
+        mut visit: impl FnMut(&Self, usize) -> Result<(), SchematicError>,
+    ) -> Result<(), SchematicError> {
+        let radius = self.config.adjacency.radius();
+        let wraps = self.config.adjacency.wraps();
+
+        // the window may start partway through the schematic
+        let mut next_to_visit = if wraps { radius } else { self.first_row };
+        let mut loaded = self.first_row;
+
+        for line in lines {
+            let row = Row::new(line);
+
+            // the first rows are needed again once the last rows are scanned
+            if wraps && loaded < 2 * radius {
+                self.head.push(row.clone());
+            }
+
+            self.rows.push_back(row);
+            loaded += 1;
+
+            while next_to_visit + radius < loaded {
+                visit(self, next_to_visit)?;
+                next_to_visit += 1;
+
+                // drop rows that the next row to visit can't reach
+                while self.first_row + radius < next_to_visit {
+                    self.rows.pop_front();
+                    self.first_row += 1;
+                }
+            }
+        }
+
+        self.total_rows = Some(loaded);
+
+        // the last rows have no rows below them
+        while next_to_visit < loaded {
+            visit(self, next_to_visit)?;
+            next_to_visit += 1;
+        }
+
+        if wraps {
+            for row in 0..min(radius, loaded) {
+                visit(self, row)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// returns the row at index along with its index, if the row is in view. When adjacency
+    /// wraps, indexes before the first row or past the last row wrap around to the other side.
+    fn row(&self, index: isize) -> Option<(usize, &Row)> {
+        let index = match self.total_rows {
+            Some(total) if total > 0 && self.config.adjacency.wraps() => {
+                index.rem_euclid(total as isize) as usize
+            }
+            _ => usize::try_from(index).ok()?,
+        };
+
+        if index >= self.first_row && index < self.first_row + self.rows.len() {
+            return Some((index, &self.rows[index - self.first_row]));
+        }
+
+        self.head.get(index).map(|r| (index, r))
+    }
+
+    /// scans a row for part numbers, scans for potential gears around part numbers
+    fn scan_for_part_numbers(
+        &self,
+        row: usize,
         prospective_gears: &mut HashMap<Coordinate, ProspectiveGear>,
         part_numbers: &mut Vec<NumberInfo>,
-    ) -> () {
-        if let Some(line) = &self.middle {
-            let nums = scan_for_numbers(&line, self.row);
+    ) -> Result<(), SchematicError> {
+        if let Some((_, line)) = self.row(row as isize) {
+            let nums = scan_for_numbers(line, row)?;
 
             for num in nums.into_iter() {
                 // look for surrounding symbols
@@ -113,18 +342,22 @@ impl Window {
 
                 // if there are surrounding symbols, num is a part number
                 if !surrounding_symbols.is_empty() {
-                    // look for asterisks around the part number
+                    // look for gear symbols around the part number
                     for symbol in surrounding_symbols.into_iter() {
-                        if symbol.symbol == '*' {
-                            // increment the number of part numbers around the asterisk
+                        if self.config.is_gear(&symbol.symbol) {
+                            let aggregate = self.config.gear_rule.aggregate;
+
+                            // increment the number of part numbers around the gear symbol
                             let gear = prospective_gears
                                 .entry(symbol.coordinate.clone())
                                 .or_insert(ProspectiveGear {
                                     part_numbers_count: 0,
-                                    gear_ratio: 1,
+                                    gear_ratio: Some(aggregate.identity()),
                                 });
                             gear.part_numbers_count += 1;
-                            gear.gear_ratio *= num.number;
+                            gear.gear_ratio = gear
+                                .gear_ratio
+                                .and_then(|ratio| aggregate.combine(ratio, num.number));
                         };
                     }
 
@@ -132,136 +365,119 @@ impl Window {
                 }
             }
         }
+
+        Ok(())
     }
 
+    /// returns the symbols adjacent to any digit of the number, ordered by row and then column
     fn surrounding_symbols(&self, number_info: &NumberInfo) -> Vec<Symbol> {
-        let mut result = Vec::new();
-        if let Some(m) = &self.middle {
-            // check left
-            // guard prevents usize subtraction from panicking
-            if number_info.coordinate.column > 0 {
-                let left_idx = number_info.coordinate.column - 1;
-                if let Some(c) = m.get(left_idx) {
-                    if is_symbol(*c) {
-                        let symbol = Symbol {
-                            symbol: *c,
-                            coordinate: Coordinate {
-                                row: self.row,
-                                column: left_idx,
-                            },
-                        };
-                        result.push(symbol);
+        let row = number_info.coordinate.row;
+        let start = number_info.coordinate.column;
+        let end = start + number_info.num_digits;
+        let wraps = self.config.adjacency.wraps();
+        let offsets = self.config.adjacency.offsets();
+
+        // keyed by (row, column) so each symbol is reported once, in order
+        let mut found: BTreeMap<(usize, usize), Symbol> = BTreeMap::new();
+
+        for digit_column in start..end {
+            for (row_offset, column_offset) in offsets.iter() {
+                let Some((r, line)) = self.row(row as isize + row_offset) else {
+                    continue;
+                };
+
+                let column = digit_column as isize + column_offset;
+                let column = if wraps && !line.is_empty() {
+                    column.rem_euclid(line.len() as isize) as usize
+                } else {
+                    match usize::try_from(column) {
+                        Ok(c) => c,
+                        Err(_) => continue,
                     }
-                }
-            }
+                };
 
-            // check right
-            let right_idx = number_info.coordinate.column + number_info.num_digits;
-            if let Some(c) = m.get(right_idx) {
-                if is_symbol(*c) {
-                    let symbol = Symbol {
-                        symbol: *c,
-                        coordinate: Coordinate {
-                            row: self.row,
-                            column: right_idx,
-                        },
-                    };
-                    result.push(symbol);
+                // the number's own digits aren't its neighbours
+                if r == row && column >= start && column < end {
+                    continue;
                 }
-            }
-        }
-
-        // check top
-        if let Some(p) = &self.previous {
-            let left_boundary = if number_info.coordinate.column == 0 {
-                0
-            } else {
-                number_info.coordinate.column - 1
-            };
-
-            let right_boundary = min(left_boundary + number_info.num_digits + 2, p.len());
-
-            for (idx, c) in p[left_boundary..right_boundary].iter().enumerate() {
-                if is_symbol(*c) {
-                    let symbol = Symbol {
-                        symbol: *c,
-                        coordinate: Coordinate {
-                            row: self.row - 1,
-                            column: idx + left_boundary,
-                        },
-                    };
-                    result.push(symbol);
-                }
-            }
-        }
 
-        // check bottom
-        if let Some(t) = &self.next {
-            let left_boundary = if number_info.coordinate.column == 0 {
-                0
-            } else {
-                number_info.coordinate.column - 1
-            };
-
-            let right_boundary = min(left_boundary + number_info.num_digits + 2, t.len());
-
-            for (idx, c) in t[left_boundary..right_boundary].iter().enumerate() {
-                if is_symbol(*c) {
-                    let symbol = Symbol {
-                        symbol: *c,
-                        coordinate: Coordinate {
-                            row: self.row + 1,
-                            column: idx + left_boundary,
-                        },
-                    };
-                    result.push(symbol);
+                if let Some(symbol) = symbol_at(line, r, column, &self.config) {
+                    found.insert((r, column), symbol);
                 }
             }
         }
 
-        return result;
+        found.into_values().collect()
     }
 }
 
-/// return gears that were found
-fn gears(prospective_gears: HashMap<Coordinate, ProspectiveGear>) -> Vec<ProspectiveGear> {
+/// return gears that were found, or an error if the ratio of one of them is out of range
+fn gears(
+    prospective_gears: HashMap<Coordinate, ProspectiveGear>,
+    gear_rule: &GearRule,
+) -> Result<Vec<Gear>, SchematicError> {
     return prospective_gears
         .into_iter()
-        .map(|(_k, v)| v)
-        .filter(|pg| pg.part_numbers_count == 2)
+        .filter(|(_, pg)| gear_rule.accepts(pg.part_numbers_count))
+        .map(|(coordinate, pg)| match pg.gear_ratio {
+            Some(gear_ratio) => Ok(Gear {
+                coordinate,
+                gear_ratio,
+            }),
+            None => Err(SchematicError::GearRatioOutOfRange { coordinate }),
+        })
         .collect();
 }
 
+/// sums values, or returns an error if the sum is out of range
+fn checked_sum(mut values: impl Iterator<Item = u64>) -> Result<u64, SchematicError> {
+    values.try_fold(0_u64, |acc, v| {
+        acc.checked_add(v).ok_or(SchematicError::SumOutOfRange)
+    })
+}
+
 // returns a vector of tuples of numbers and their starting indexes
-fn scan_for_numbers(line: &Vec<char>, row: usize) -> Vec<NumberInfo> {
+fn scan_for_numbers(line: &Row, row: usize) -> Result<Vec<NumberInfo>, SchematicError> {
     let mut result = Vec::new();
 
     let mut consecutive_digits: Vec<u32> = Vec::new();
 
-    let mut chars_iter = line.iter();
+    let mut graphemes_iter = line.graphemes();
 
     let mut number_start: Option<usize> = None;
 
     let mut idx = 0;
 
     loop {
-        let next_char = chars_iter.next();
+        let next_grapheme = graphemes_iter.next();
 
-        match next_char {
-            Some(c) => {
-                if let Some(d) = c.to_digit(10) {
+        match next_grapheme {
+            Some(g) => {
+                if let Some(d) = to_digit(g) {
                     consecutive_digits.push(d);
 
                     if number_start.is_none() {
                         number_start = Some(idx);
                     }
                 } else {
-                    flush_number(&mut consecutive_digits, &mut result, number_start, row);
+                    flush_number(
+                        &mut consecutive_digits,
+                        &mut result,
+                        number_start,
+                        line,
+                        row,
+                    )?;
                     number_start = None;
                 }
             }
             None => {
-                flush_number(&mut consecutive_digits, &mut result, number_start, row);
+                flush_number(
+                    &mut consecutive_digits,
+                    &mut result,
+                    number_start,
+                    line,
+                    row,
+                )?;
 
                 break;
             }
@@ -269,87 +485,148 @@ fn scan_for_numbers(line: &Vec<char>, row: usize) -> Vec<NumberInfo> {
         idx += 1;
     }
 
-    return result;
+    return Ok(result);
+}
+
+fn scan_for_symbols(line: &Row, row: usize, config: &SchematicConfig) -> Vec<Symbol> {
+    (0..line.len())
+        .filter_map(|column| symbol_at(line, row, column, config))
+        .collect()
+}
+
+/// returns the symbol in the column of the line, if there is one
+fn symbol_at(line: &Row, row: usize, column: usize, config: &SchematicConfig) -> Option<Symbol> {
+    let grapheme = line.get(column)?;
+
+    if !config.is_symbol(grapheme) {
+        return None;
+    }
+
+    Some(Symbol {
+        symbol: grapheme.to_string(),
+        coordinate: Coordinate { row, column },
+        byte_span: line.byte_span(column)?,
+    })
 }
 
 fn flush_number(
     consecutive_digits: &mut Vec<u32>,
     result: &mut Vec<NumberInfo>,
     number_start: Option<usize>,
+    line: &Row,
     row: usize,
-) -> () {
+) -> Result<(), SchematicError> {
     if let Some(n) = number_start {
+        let coordinate = Coordinate { row, column: n };
         let len = consecutive_digits.len();
-        let number: u32 = (0..len)
-            .rev()
-            .zip(consecutive_digits.iter())
-            .map(|(power, digit)| 10_u32.pow(u32::try_from(power).expect("error")) * digit)
-            .sum();
+
+        // digits are always a single byte
+        let byte_start = line.byte_span(n).map_or(0, |span| span.start);
+        let byte_span = byte_start..byte_start + len;
+
+        // shift in one digit at a time, checking that the number still fits
+        let number = consecutive_digits
+            .iter()
+            .try_fold(0_u64, |acc, digit| {
+                acc.checked_mul(10)?.checked_add(u64::from(*digit))
+            })
+            .ok_or_else(|| SchematicError::NumberOutOfRange {
+                coordinate: coordinate.clone(),
+            })?;
 
         result.push(NumberInfo {
             number,
-            coordinate: Coordinate { row, column: n },
+            coordinate,
             num_digits: len,
+            byte_span,
         });
     }
     consecutive_digits.clear();
-}
-
-fn is_symbol(c: char) -> bool {
-    !c.is_digit(10) && c != '.'
+    Ok(())
 }
 
 struct ProcessingResult {
-    gears: Vec<ProspectiveGear>,
+    gears: Vec<Gear>,
     part_numbers: Vec<NumberInfo>,
 }
 
 #[derive(PartialEq, Debug)]
-struct NumberInfo {
-    number: u32,
-    coordinate: Coordinate,
-    num_digits: usize,
+pub struct NumberInfo {
+    pub number: u64,
+    pub coordinate: Coordinate,
+    pub num_digits: usize,
+
+    /// the bytes of the line the number was read from
+    pub byte_span: Range<usize>,
 }
 
 #[derive(Eq, Hash, PartialEq, Debug, Clone)]
-struct Coordinate {
-    row: usize,
-    column: usize,
+pub struct Coordinate {
+    pub row: usize,
+
+    /// the index of the grapheme in the row, i.e. the column it is displayed in
+    pub column: usize,
 }
 
 #[derive(PartialEq, Debug)]
-struct Symbol {
-    symbol: char,
-    coordinate: Coordinate,
+pub struct Symbol {
+    /// the grapheme that makes up the symbol
+    pub symbol: String,
+    pub coordinate: Coordinate,
+
+    /// the bytes of the line the symbol was read from
+    pub byte_span: Range<usize>,
 }
 
 #[derive(Debug)]
 struct ProspectiveGear {
     part_numbers_count: u32,
-    gear_ratio: u32,
+
+    /// None once the ratio no longer fits in a u64, which is only an error if the gear counts
+    gear_ratio: Option<u64>,
+}
+
+impl ProspectiveGear {
+    /// combines the part numbers counted for the same gear by another scan
+    fn merge(&mut self, other: &ProspectiveGear, aggregate: &Aggregate) {
+        self.part_numbers_count += other.part_numbers_count;
+        self.gear_ratio = self
+            .gear_ratio
+            .zip(other.gear_ratio)
+            .and_then(|(a, b)| aggregate.combine(a, b));
+    }
+}
+
+#[derive(Debug)]
+struct Gear {
+    coordinate: Coordinate,
+    gear_ratio: u64,
 }
 
 #[cfg(test)]
 mod tests {
     use crate::*;
+    use std::collections::HashSet;
 
     #[test]
     fn scan_for_numbers_works() {
-        let input: Vec<char> = "467..114..".chars().collect();
+        let input = Row::new(String::from("467..114.."));
         let row = 0;
-        let result = scan_for_numbers(&input, row);
+        let result = scan_for_numbers(&input, row).unwrap();
         assert_eq!(
             result,
             vec![
                 NumberInfo {
                     number: 467,
                     coordinate: Coordinate { row, column: 0 },
-                    num_digits: 3
+                    num_digits: 3,
+                    byte_span: 0..3
                 },
                 NumberInfo {
                     number: 114,
                     coordinate: Coordinate { column: 5, row },
-                    num_digits: 3
+                    num_digits: 3,
+                    byte_span: 5..8
                 }
             ]
         );
@@ -365,6 +642,7 @@ mod tests {
                 number: 58,
                 coordinate: Coordinate { column: 7, row },
                 num_digits: 2,
+                byte_span: 7..9,
             };
 
             assert_eq!(window.surrounding_symbols(&part_number), vec![]);
@@ -376,13 +654,15 @@ mod tests {
                 coordinate: Coordinate { row, column: 2 },
                 number: 35,
                 num_digits: 2,
+                byte_span: 2..4,
             };
 
             assert_eq!(
                 window.surrounding_symbols(&part_number),
                 vec![Symbol {
-                    symbol: '*',
-                    coordinate: Coordinate { row: 0, column: 3 }
+                    symbol: String::from("*"),
+                    coordinate: Coordinate { row: 0, column: 3 },
+                    byte_span: 3..4
                 }]
             );
         }
@@ -393,18 +673,305 @@ mod tests {
                 coordinate: Coordinate { row, column: 2 },
                 number: 592,
                 num_digits: 3,
+                byte_span: 2..5,
             };
 
             assert_eq!(
                 window.surrounding_symbols(&part_number),
                 vec![Symbol {
-                    symbol: '+',
-                    coordinate: Coordinate { row: 0, column: 5 }
+                    symbol: String::from("+"),
+                    coordinate: Coordinate { row: 0, column: 5 },
+                    byte_span: 5..6
                 }]
             );
         }
     }
 
+    #[test]
+    fn configured_gear_rule_works() {
+        // every '*' next to at least one part number, scored by its largest part number
+        let config = SchematicConfig {
+            gear_rule: GearRule {
+                count: PartCount::AtLeast(1),
+                aggregate: Aggregate::Max,
+            },
+            ..SchematicConfig::default()
+        };
+        assert_eq!(
+            pt2_with_config("test_input", &config).unwrap(),
+            467 + 617 + 755
+        );
+
+        // only '#' and '$' are symbols, so 467, 35, 617, 592 and 755 are no longer parts
+        let config = SchematicConfig {
+            symbols: SymbolSet::Only(HashSet::from(['#', '$'])),
+            ..SchematicConfig::default()
+        };
+        assert_eq!(pt1_with_config("test_input", &config).unwrap(), 633 + 664);
+    }
+
+    #[test]
+    fn graph_works() {
+        let graph = graph("test_input", &SchematicConfig::default()).unwrap();
+
+        assert_eq!(graph.numbers().len(), 10);
+        assert_eq!(graph.symbols().len(), 6);
+
+        let touching: Vec<u64> = graph
+            .numbers_touching(&Coordinate { row: 8, column: 5 })
+            .iter()
+            .map(|n| n.number)
+            .collect();
+        assert_eq!(touching, vec![755, 598]);
+
+        let isolated: Vec<u64> = graph.isolated_numbers().iter().map(|n| n.number).collect();
+        assert_eq!(isolated, vec![114, 58]);
+
+        // the 6 symbols each form a component, plus the 2 isolated numbers
+        assert_eq!(graph.connected_components().len(), 8);
+
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("graph schematic {"));
+        assert!(dot.contains("n0 -- s0;"));
+    }
+
+    #[test]
+    fn render_schematic_works() {
+        let config = SchematicConfig::default();
+
+        let ansi = render_schematic("test_input", &config, RenderMode::Ansi).unwrap();
+        let lines: Vec<&str> = ansi.lines().collect();
+        assert_eq!(lines.len(), 10);
+        // 467 is a part, 114 is not
+        assert!(lines[0].starts_with("\x1b[32m4\x1b[0m"));
+        assert!(lines[0].contains("\x1b[31m1\x1b[0m"));
+        assert!(lines[1].ends_with("[3: ratio 16345]"));
+
+        let html = render_schematic("test_input", &config, RenderMode::Html).unwrap();
+        assert!(html.contains("<span class=\"gear\">*</span>"));
+        assert!(html.contains("<span class=\"symbol\">#</span>"));
+        assert!(html.contains("[5: ratio 451490]"));
+    }
+
+    #[test]
+    fn logical_width_works() {
+        // hard-wrap the 10 character rows of test_input at 4 characters
+        let wrapped: Vec<String> = std::fs::read_to_string("test_input")
+            .unwrap()
+            .lines()
+            .flat_map(|l| {
+                let chars: Vec<char> = l.chars().collect();
+                chars
+                    .chunks(4)
+                    .map(|c| c.iter().collect::<String>())
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        let config = SchematicConfig {
+            logical_width: Some(10),
+            ..SchematicConfig::default()
+        };
+
+        let result = Window::new(config.clone())
+            .process_lines(&mut wrapped.iter().cloned())
+            .unwrap();
+        let part_sum: u64 = result.part_numbers.iter().map(|n| n.number).sum();
+        let ratio_sum: u64 = result.gears.iter().map(|g| g.gear_ratio).sum();
+        assert_eq!(part_sum, 4361);
+        assert_eq!(ratio_sum, 467835);
+
+        // 633 starts at logical (2, 6), which is column 2 of physical line 7
+        let graph = Window::new(config.clone())
+            .build_graph(&mut wrapped.iter().cloned())
+            .unwrap();
+        let n633 = graph.numbers().iter().find(|n| n.number == 633).unwrap();
+        assert_eq!(n633.coordinate, Coordinate { row: 7, column: 2 });
+        // the '*' at logical (1, 3) is at physical (3, 3)
+        assert_eq!(
+            graph
+                .numbers_touching(&Coordinate { row: 3, column: 3 })
+                .len(),
+            2
+        );
+
+        // rows wrapped at 3 characters cross the end of each 10 character row
+        let misaligned: Vec<String> = wrapped
+            .concat()
+            .chars()
+            .collect::<Vec<_>>()
+            .chunks(3)
+            .map(|c| c.iter().collect())
+            .collect();
+        assert!(matches!(
+            Window::new(config.clone()).process_lines(&mut misaligned.iter().cloned()),
+            Err(SchematicError::MisalignedRow { line: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn adjacency_works() {
+        let lines = ["*....", ".12..", "....#", "..3.."];
+        let to_row = |s: &&str| Row::new(s.to_string());
+
+        let window = |adjacency: Adjacency| Window {
+            rows: lines.iter().map(to_row).collect(),
+            first_row: 0,
+            head: lines.iter().map(to_row).collect(),
+            total_rows: Some(lines.len()),
+            config: SchematicConfig {
+                adjacency,
+                ..SchematicConfig::default()
+            },
+        };
+
+        let twelve = NumberInfo {
+            number: 12,
+            coordinate: Coordinate { row: 1, column: 1 },
+            num_digits: 2,
+            byte_span: 1..3,
+        };
+        let three = NumberInfo {
+            number: 3,
+            coordinate: Coordinate { row: 3, column: 2 },
+            num_digits: 1,
+            byte_span: 2..3,
+        };
+        let symbols = |adjacency: Adjacency, number: &NumberInfo| -> Vec<String> {
+            window(adjacency)
+                .surrounding_symbols(number)
+                .into_iter()
+                .map(|s| s.symbol)
+                .collect()
+        };
+
+        assert_eq!(symbols(Adjacency::EightNeighbour, &twelve), vec!["*"]);
+        assert_eq!(symbols(Adjacency::Diagonal, &twelve), vec!["*"]);
+        assert!(symbols(Adjacency::Orthogonal, &twelve).is_empty());
+        assert_eq!(symbols(Adjacency::Manhattan(2), &twelve), vec!["*"]);
+        assert_eq!(symbols(Adjacency::Manhattan(3), &twelve), vec!["*", "#"]);
+
+        assert!(symbols(Adjacency::EightNeighbour, &three).is_empty());
+        assert_eq!(symbols(Adjacency::Manhattan(3), &three), vec!["#"]);
+        // row 0 is below the last row when the schematic wraps
+        assert!(symbols(Adjacency::Toroidal, &three).is_empty());
+        assert_eq!(symbols(Adjacency::Toroidal, &twelve), vec!["*"]);
+    }
+
+    #[test]
+    fn toroidal_scan_works() {
+        let lines = ["1...2", ".....", "3...*"];
+        let config = SchematicConfig {
+            adjacency: Adjacency::Toroidal,
+            gear_rule: GearRule {
+                count: PartCount::Exactly(3),
+                aggregate: Aggregate::Sum,
+            },
+            ..SchematicConfig::default()
+        };
+
+        // the '*' in the corner touches the other three corners by wrapping around
+        let result = Window::new(config)
+            .process_lines(&mut lines.iter().map(|l| l.to_string()))
+            .unwrap();
+        let part_sum: u64 = result.part_numbers.iter().map(|n| n.number).sum();
+        assert_eq!(part_sum, 6);
+        assert_eq!(result.gears.len(), 1);
+        assert_eq!(result.gears[0].gear_ratio, 6);
+
+        // the standard neighbourhood doesn't wrap
+        let result = Window::new(SchematicConfig::default())
+            .process_lines(&mut lines.iter().map(|l| l.to_string()))
+            .unwrap();
+        assert!(result.part_numbers.is_empty());
+    }
+
+    #[test]
+    fn out_of_range_works() {
+        let max = u64::MAX.to_string();
+        let too_big = "18446744073709551616";
+
+        let process = |lines: &[&str]| {
+            Window::new(SchematicConfig::default())
+                .process_lines(&mut lines.iter().map(|l| l.to_string()))
+        };
+
+        // u64::MAX itself still fits
+        let result = process(&[&format!("{}*", max)]).unwrap();
+        assert_eq!(result.part_numbers[0].number, u64::MAX);
+
+        assert!(matches!(
+            process(&[".......", &format!("..{}", too_big)]),
+            Err(SchematicError::NumberOutOfRange {
+                coordinate: Coordinate { row: 1, column: 2 }
+            })
+        ));
+
+        // the product of the two parts is too big for a u64
+        assert!(matches!(
+            process(&[&format!("{}*2", max)]),
+            Err(SchematicError::GearRatioOutOfRange {
+                coordinate: Coordinate { row: 0, column: 20 }
+            })
+        ));
+
+        // a gear with too many parts is only an error if it would have counted
+        assert!(process(&[&format!("{}*2", max), "....................9"]).is_ok());
+
+        assert!(matches!(
+            checked_sum([u64::MAX, 1].into_iter()),
+            Err(SchematicError::SumOutOfRange)
+        ));
+    }
+
+    #[test]
+    fn unicode_columns_work() {
+        // box-drawing characters are 3 bytes each, and 'e' with a combining accent is 1 grapheme
+        let lines = ["┌──┐e\u{301}", "│42├17", "└──┘.."];
+
+        let graph = Window::new(SchematicConfig::default())
+            .build_graph(&mut lines.iter().map(|l| l.to_string()))
+            .unwrap();
+
+        let numbers: Vec<(u64, &Coordinate, &Range<usize>)> = graph
+            .numbers()
+            .iter()
+            .map(|n| (n.number, &n.coordinate, &n.byte_span))
+            .collect();
+        assert_eq!(
+            numbers,
+            vec![
+                (42, &Coordinate { row: 1, column: 1 }, &(3..5)),
+                (17, &Coordinate { row: 1, column: 4 }, &(8..10)),
+            ]
+        );
+
+        // 17 touches the '├' to its left, the corners diagonal to it and the accented 'e'
+        let symbols: Vec<(&str, &Range<usize>)> = graph
+            .symbols_touching(&graph.numbers()[1])
+            .iter()
+            .map(|s| (s.symbol.as_str(), &s.byte_span))
+            .collect();
+        assert_eq!(
+            symbols,
+            vec![
+                ("┐", &(9..12)),
+                ("e\u{301}", &(12..15)),
+                ("├", &(5..8)),
+                ("┘", &(9..12))
+            ]
+        );
+
+        // non-ASCII graphemes are symbols even when only some ASCII symbols are
+        let config = SchematicConfig {
+            symbols: SymbolSet::Only(HashSet::from(['#'])),
+            ..SchematicConfig::default()
+        };
+        assert!(config.is_symbol("│"));
+        assert!(!config.is_symbol("e"));
+        assert!(!config.is_symbol("."));
+    }
+
     #[test]
     fn pt1_works() {
         assert_eq!(pt1().unwrap(), 519444);
@@ -418,13 +985,21 @@ mod tests {
 
     /// constructs a window from strings, useful for testing
     fn test_window(previous: Option<&str>, middle: Option<&str>, next: Option<&str>) -> Window {
-        let to_chars = |s: &str| -> Vec<char> { s.chars().collect() };
+        let to_row = |s: &str| Row::new(s.to_string());
+
+        // the middle line is row 1
+        let first_row = if previous.is_some() { 0 } else { 1 };
 
         Window {
-            previous: previous.map(to_chars),
-            middle: middle.map(to_chars),
-            next: next.map(to_chars),
-            row: 1,
+            rows: [previous, middle, next]
+                .into_iter()
+                .flatten()
+                .map(to_row)
+                .collect(),
+            first_row,
+            head: Vec::new(),
+            total_rows: None,
+            config: SchematicConfig::default(),
         }
     }
 }
@@ -0,0 +1,294 @@
+use std::cmp::min;
+use std::collections::HashMap;
+use std::thread;
+
+use crate::wrap::{LineMap, LogicalLines};
+use crate::{
+    checked_sum, gears, Coordinate, ProspectiveGear, SchematicConfig, SchematicError, Window,
+};
+
+/// The totals that pt1 and pt2 report for a schematic
+#[derive(Debug, Default, PartialEq)]
+pub struct SchematicSums {
+    pub part_numbers: u64,
+    pub gear_ratios: u64,
+}
+
+/// The result of scanning a band of rows
+#[derive(Default)]
+struct BandResult {
+    sums: SchematicSums,
+
+    /// gears near the edges of the band, which numbers in the neighbouring bands may also touch
+    boundary_gears: HashMap<Coordinate, ProspectiveGear>,
+}
+
+/// Splits the rows into one band per thread and scans the bands in parallel. Each band also
+/// reads the rows within the adjacency radius of its edges, but only scans its own rows for
+/// numbers. Gears that only the band's own numbers can reach are totalled by the band, the rest
+/// are merged across bands so every gear is counted exactly once.
+///
+/// Adjacency that wraps around the schematic is scanned as a single band.
+pub fn process_parallel(
+    lines: &[String],
+    config: &SchematicConfig,
+    threads: usize,
+) -> Result<SchematicSums, SchematicError> {
+    match config.logical_width {
+        Some(width) => {
+            // the logical rows are only used for totals, so the line map can be thrown away
+            let mut line_map = LineMap::new();
+            let rows: Vec<String> = LogicalLines::new(lines.iter().cloned(), width, &mut line_map)
+                .collect::<Result<_, _>>()?;
+            process_rows_parallel(rows.len(), &|row| rows[row].clone(), config, threads)
+        }
+        None => process_rows_parallel(lines.len(), &|row| lines[row].clone(), config, threads),
+    }
+}
+
+/// like process_parallel, but reads each row from row_at so the schematic doesn't have to be
+/// held in memory
+pub(crate) fn process_rows_parallel(
+    total_rows: usize,
+    row_at: &(dyn Fn(usize) -> String + Sync),
+    config: &SchematicConfig,
+    threads: usize,
+) -> Result<SchematicSums, SchematicError> {
+    let bands = if config.adjacency.wraps() {
+        1
+    } else {
+        threads.clamp(1, total_rows.max(1))
+    };
+    let band_size = total_rows.div_ceil(bands).max(1);
+
+    let results: Vec<Result<BandResult, SchematicError>> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..total_rows)
+            .step_by(band_size)
+            .map(|start| {
+                let end = min(start + band_size, total_rows);
+                scope.spawn(move || scan_band(total_rows, row_at, config, start, end))
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .map(|h| h.join().expect("band scan panicked"))
+            .collect()
+    });
+
+    let aggregate = config.gear_rule.aggregate;
+    let mut sums = SchematicSums::default();
+    let mut boundary_gears: HashMap<Coordinate, ProspectiveGear> = HashMap::new();
+
+    for result in results {
+        let result = result?;
+        sums.part_numbers = checked_sum([sums.part_numbers, result.sums.part_numbers].into_iter())?;
+        sums.gear_ratios = checked_sum([sums.gear_ratios, result.sums.gear_ratios].into_iter())?;
+
+        for (coordinate, gear) in result.boundary_gears {
+            boundary_gears
+                .entry(coordinate)
+                .or_insert(ProspectiveGear {
+                    part_numbers_count: 0,
+                    gear_ratio: Some(aggregate.identity()),
+                })
+                .merge(&gear, &aggregate);
+        }
+    }
+
+    let boundary_ratios = gears(boundary_gears, &config.gear_rule)?;
+    sums.gear_ratios = checked_sum(
+        boundary_ratios
+            .iter()
+            .map(|g| g.gear_ratio)
+            .chain([sums.gear_ratios]),
+    )?;
+
+    Ok(sums)
+}
+
+/// scans the numbers in rows start..end
+fn scan_band(
+    total_rows: usize,
+    row_at: &(dyn Fn(usize) -> String + Sync),
+    config: &SchematicConfig,
+    start: usize,
+    end: usize,
+) -> Result<BandResult, SchematicError> {
+    let radius = config.adjacency.radius();
+    let context_start = start.saturating_sub(radius);
+    let context_end = min(end + radius, total_rows);
+
+    let mut window = Window::new(config.clone());
+    window.first_row = context_start;
+
+    let mut prospective_gears = HashMap::new();
+    let mut part_numbers = Vec::new();
+    let mut result = BandResult::default();
+
+    window.scan(
+        &mut (context_start..context_end).map(row_at),
+        |window, row| {
+            if row >= start && row < end {
+                window.scan_for_part_numbers(row, &mut prospective_gears, &mut part_numbers)?;
+
+                result.sums.part_numbers = checked_sum(
+                    part_numbers
+                        .drain(..)
+                        .map(|n| n.number)
+                        .chain([result.sums.part_numbers]),
+                )?;
+            }
+            Ok(())
+        },
+    )?;
+
+    // gears within the radius of a band edge can be touched by numbers outside of the band,
+    // unless that edge is the edge of the schematic
+    let is_boundary = |row: usize| {
+        (start > 0 && row < start + radius) || (end < total_rows && row + radius >= end)
+    };
+
+    let (boundary, owned): (HashMap<_, _>, HashMap<_, _>) = prospective_gears
+        .into_iter()
+        .partition(|(coordinate, _)| !config.adjacency.wraps() && is_boundary(coordinate.row));
+
+    result.sums.gear_ratios = checked_sum(
+        gears(owned, &config.gear_rule)?
+            .iter()
+            .map(|g| g.gear_ratio),
+    )?;
+    result.boundary_gears = boundary;
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Adjacency, Aggregate, GearRule, PartCount};
+
+    /// sums a schematic the sequential way
+    fn sequential_sums(lines: &[String], config: &SchematicConfig) -> SchematicSums {
+        let result = Window::new(config.clone())
+            .process_lines(&mut lines.iter().cloned())
+            .unwrap();
+        SchematicSums {
+            part_numbers: result.part_numbers.iter().map(|n| n.number).sum(),
+            gear_ratios: result.gears.iter().map(|g| g.gear_ratio).sum(),
+        }
+    }
+
+    /// generates a row of a pseudo-random schematic. One in every `sparsity` cells starts a
+    /// symbol or a number of up to 3 digits.
+    fn generated_row(row: usize, width: usize, sparsity: u64) -> String {
+        let mut line = String::with_capacity(width);
+
+        while line.len() < width {
+            let cell = splitmix((row * width + line.len()) as u64);
+
+            if !cell.is_multiple_of(sparsity) {
+                line.push('.');
+                continue;
+            }
+
+            match (cell / sparsity) % 5 {
+                0 => line.push('*'),
+                1 => line.push('#'),
+                len => {
+                    // numbers are followed by a blank so they don't run together
+                    let number = (cell >> 32) % 10_u64.pow(len as u32 - 1);
+                    line.push_str(&number.to_string());
+                    line.push('.');
+                }
+            }
+        }
+
+        line.truncate(width);
+        line
+    }
+
+    fn splitmix(seed: u64) -> u64 {
+        let mut z = seed.wrapping_add(0x9e3779b97f4a7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    #[test]
+    fn process_parallel_works() {
+        let input: Vec<String> = std::fs::read_to_string("input")
+            .unwrap()
+            .lines()
+            .map(String::from)
+            .collect();
+        let config = SchematicConfig::default();
+
+        assert_eq!(
+            process_parallel(&input, &config, 4).unwrap(),
+            SchematicSums {
+                part_numbers: 519444,
+                gear_ratios: 74528807
+            }
+        );
+
+        let generated: Vec<String> = (0..300).map(|row| generated_row(row, 300, 3)).collect();
+
+        let configs = [
+            SchematicConfig::default(),
+            SchematicConfig {
+                adjacency: Adjacency::Manhattan(2),
+                gear_rule: GearRule {
+                    count: PartCount::AtLeast(1),
+                    aggregate: Aggregate::Max,
+                },
+                ..SchematicConfig::default()
+            },
+            SchematicConfig {
+                adjacency: Adjacency::Toroidal,
+                ..SchematicConfig::default()
+            },
+        ];
+
+        for config in configs.iter() {
+            let expected = sequential_sums(&generated, config);
+            // 300 threads gives bands of a single row
+            for threads in [1, 2, 7, 300] {
+                assert_eq!(
+                    process_parallel(&generated, config, threads).unwrap(),
+                    expected
+                );
+            }
+        }
+    }
+
+    /// run with `cargo test --release -- --ignored`, takes a couple of minutes
+    #[test]
+    #[ignore]
+    fn process_parallel_huge_schematic_works() {
+        let size = 100_000;
+        let config = SchematicConfig::default();
+
+        let expected = sequential_sums_streaming(size, &config);
+        let result = process_rows_parallel(
+            size,
+            &|row| generated_row(row, size, 1000),
+            &config,
+            thread::available_parallelism().map_or(8, |n| n.get()),
+        )
+        .unwrap();
+
+        assert_eq!(result, expected);
+    }
+
+    /// sums a generated schematic the sequential way, without holding every row in memory
+    fn sequential_sums_streaming(size: usize, config: &SchematicConfig) -> SchematicSums {
+        let result = Window::new(config.clone())
+            .process_lines(&mut (0..size).map(|row| generated_row(row, size, 1000)))
+            .unwrap();
+        SchematicSums {
+            part_numbers: result.part_numbers.iter().map(|n| n.number).sum(),
+            gear_ratios: result.gears.iter().map(|g| g.gear_ratio).sum(),
+        }
+    }
+}
@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::row::Row;
+use crate::{Coordinate, SchematicConfig, SchematicError, SchematicGraph};
+
+const ANSI_RESET: &str = "\x1b[0m";
+const ANSI_PART: &str = "\x1b[32m";
+const ANSI_NON_PART: &str = "\x1b[31m";
+const ANSI_SYMBOL: &str = "\x1b[36m";
+const ANSI_GEAR: &str = "\x1b[1;30;43m";
+
+const HTML_STYLE: &str = "<style>
+.part { color: green; }
+.non-part { color: red; }
+.symbol { color: teal; }
+.gear { background: gold; font-weight: bold; }
+</style>
+";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RenderMode {
+    /// colours the schematic with ANSI escape codes, for printing to a terminal
+    Ansi,
+
+    /// a standalone HTML snippet, for attaching to reviews
+    Html,
+}
+
+/// What the scanner decided about a single character of the schematic
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Cell {
+    Part,
+    NonPart,
+    Symbol,
+    Gear,
+}
+
+/// Reprints the schematic with part numbers, non-part numbers, symbols and gears highlighted.
+/// Each row is followed by the ratios of the gears on that row.
+pub fn render(
+    lines: &[String],
+    graph: &SchematicGraph,
+    config: &SchematicConfig,
+    mode: RenderMode,
+) -> Result<String, SchematicError> {
+    let mut cells: HashMap<Coordinate, Cell> = HashMap::new();
+
+    let rows: Vec<Row> = lines.iter().cloned().map(Row::new).collect();
+
+    for (number, is_part) in graph.classified_numbers() {
+        let cell = if is_part { Cell::Part } else { Cell::NonPart };
+
+        // walk along the digits, continuing onto the next line if the number was wrapped
+        let mut coordinate = number.coordinate.clone();
+        for _ in 0..number.num_digits {
+            cells.insert(coordinate.clone(), cell);
+            coordinate.column += 1;
+            if coordinate.column >= rows.get(coordinate.row).map_or(0, Row::len) {
+                coordinate.row += 1;
+                coordinate.column = 0;
+            }
+        }
+    }
+
+    for symbol in graph.symbols() {
+        cells.insert(symbol.coordinate.clone(), Cell::Symbol);
+    }
+
+    // gear ratios, grouped by row
+    let mut gears_by_row: HashMap<usize, Vec<(usize, u64)>> = HashMap::new();
+
+    for (symbol, ratio) in graph.gears(config)? {
+        cells.insert(symbol.coordinate.clone(), Cell::Gear);
+        gears_by_row
+            .entry(symbol.coordinate.row)
+            .or_default()
+            .push((symbol.coordinate.column, ratio));
+    }
+
+    let mut output = String::new();
+
+    if mode == RenderMode::Html {
+        output.push_str(HTML_STYLE);
+        output.push_str("<pre>\n");
+    }
+
+    for (row, line) in rows.iter().enumerate() {
+        for (column, g) in line.graphemes().enumerate() {
+            let cell = cells.get(&Coordinate { row, column }).copied();
+            match mode {
+                RenderMode::Ansi => push_ansi(&mut output, g, cell),
+                RenderMode::Html => push_html(&mut output, g, cell),
+            }
+        }
+
+        if let Some(gears) = gears_by_row.get_mut(&row) {
+            gears.sort();
+            for (column, ratio) in gears.iter() {
+                let _ = write!(output, "  [{}: ratio {}]", column, ratio);
+            }
+        }
+
+        output.push('\n');
+    }
+
+    if mode == RenderMode::Html {
+        output.push_str("</pre>\n");
+    }
+
+    Ok(output)
+}
+
+fn push_ansi(output: &mut String, grapheme: &str, cell: Option<Cell>) {
+    let colour = match cell {
+        Some(Cell::Part) => ANSI_PART,
+        Some(Cell::NonPart) => ANSI_NON_PART,
+        Some(Cell::Symbol) => ANSI_SYMBOL,
+        Some(Cell::Gear) => ANSI_GEAR,
+        None => {
+            output.push_str(grapheme);
+            return;
+        }
+    };
+
+    output.push_str(colour);
+    output.push_str(grapheme);
+    output.push_str(ANSI_RESET);
+}
+
+fn push_html(output: &mut String, grapheme: &str, cell: Option<Cell>) {
+    let class = match cell {
+        Some(Cell::Part) => Some("part"),
+        Some(Cell::NonPart) => Some("non-part"),
+        Some(Cell::Symbol) => Some("symbol"),
+        Some(Cell::Gear) => Some("gear"),
+        None => None,
+    };
+
+    if let Some(class) = class {
+        let _ = write!(output, "<span class=\"{}\">", class);
+    }
+
+    for c in grapheme.chars() {
+        match c {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '>' => output.push_str("&gt;"),
+            '"' => output.push_str("&quot;"),
+            _ => output.push(c),
+        }
+    }
+
+    if class.is_some() {
+        output.push_str("</span>");
+    }
+}
@@ -0,0 +1,62 @@
+use std::ops::Range;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+/// A line of the schematic split into grapheme clusters. Each grapheme is one column, however
+/// many bytes or chars it is made of.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Row {
+    text: String,
+
+    /// the byte offset of the start of each grapheme
+    starts: Vec<usize>,
+}
+
+impl Row {
+    pub(crate) fn new(text: String) -> Self {
+        // every byte of an ASCII line is its own grapheme, which saves segmenting the line
+        let starts = if text.is_ascii() {
+            (0..text.len()).collect()
+        } else {
+            text.grapheme_indices(true).map(|(i, _)| i).collect()
+        };
+        Self { text, starts }
+    }
+
+    /// the number of columns in the row
+    pub(crate) fn len(&self) -> usize {
+        self.starts.len()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.starts.is_empty()
+    }
+
+    /// returns the grapheme in the column
+    pub(crate) fn get(&self, column: usize) -> Option<&str> {
+        self.byte_span(column).map(|span| &self.text[span])
+    }
+
+    /// returns the bytes of the row that make up the column
+    pub(crate) fn byte_span(&self, column: usize) -> Option<Range<usize>> {
+        let start = *self.starts.get(column)?;
+        let end = self
+            .starts
+            .get(column + 1)
+            .copied()
+            .unwrap_or(self.text.len());
+        Some(start..end)
+    }
+
+    pub(crate) fn graphemes(&self) -> impl Iterator<Item = &str> {
+        (0..self.len()).filter_map(|column| self.get(column))
+    }
+}
+
+/// returns the value of the digit if the grapheme is a single ASCII digit
+pub(crate) fn to_digit(grapheme: &str) -> Option<u32> {
+    match grapheme.as_bytes() {
+        [b] if b.is_ascii_digit() => Some(u32::from(b - b'0')),
+        _ => None,
+    }
+}
@@ -0,0 +1,183 @@
+use std::ops::Range;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{Coordinate, SchematicError};
+
+/// Joins hard-wrapped physical lines back into logical rows of a fixed width. The widths of the
+/// physical lines are recorded in a LineMap so coordinates can be mapped back for reporting.
+/// Every logical row must be made of whole physical lines, so a physical line that runs past the
+/// end of a row, or a last row that is cut short, is an error.
+pub(crate) struct LogicalLines<'a, I> {
+    lines: I,
+    width: usize,
+    line_map: &'a mut LineMap,
+
+    /// set once an error has been returned, so the rows stop
+    failed: bool,
+}
+
+impl<'a, I: Iterator<Item = String>> LogicalLines<'a, I> {
+    pub(crate) fn new(lines: I, width: usize, line_map: &'a mut LineMap) -> Self {
+        Self {
+            lines,
+            width,
+            line_map,
+            failed: false,
+        }
+    }
+}
+
+impl<I: Iterator<Item = String>> Iterator for LogicalLines<'_, I> {
+    type Item = Result<String, SchematicError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+
+        let mut row = String::new();
+        let mut row_width = 0;
+        let mut physical_lines = Vec::new();
+
+        while row_width < self.width {
+            match self.lines.next() {
+                Some(line) => {
+                    let width = line.graphemes(true).count();
+                    row.push_str(&line);
+                    row_width += width;
+                    physical_lines.push(PhysicalLine {
+                        width,
+                        bytes: line.len(),
+                    });
+                }
+                None => break,
+            }
+        }
+
+        if physical_lines.is_empty() {
+            return None;
+        }
+
+        if row_width != self.width {
+            self.failed = true;
+            return Some(Err(SchematicError::MisalignedRow {
+                line: self.line_map.physical_rows + physical_lines.len() - 1,
+                width: row_width,
+                logical_width: self.width,
+            }));
+        }
+
+        self.line_map.push_row(physical_lines);
+        Some(Ok(row))
+    }
+}
+
+/// The size of a physical line, in graphemes and in bytes
+#[derive(Debug)]
+struct PhysicalLine {
+    width: usize,
+    bytes: usize,
+}
+
+/// Records which physical lines make up each logical row
+#[derive(Debug, Default)]
+pub(crate) struct LineMap {
+    /// for each logical row, the physical lines it was joined from
+    rows: Vec<Vec<PhysicalLine>>,
+
+    /// for each logical row, the index of its first physical line
+    first_physical_rows: Vec<usize>,
+
+    /// the number of physical lines seen so far
+    physical_rows: usize,
+}
+
+impl LineMap {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    fn push_row(&mut self, physical_lines: Vec<PhysicalLine>) {
+        self.first_physical_rows.push(self.physical_rows);
+        self.physical_rows += physical_lines.len();
+        self.rows.push(physical_lines);
+    }
+
+    /// maps a coordinate in the logical rows to the physical line and column it was read from
+    pub(crate) fn to_physical(&self, logical: &Coordinate) -> Coordinate {
+        let mut row = self.first_physical_rows[logical.row];
+        let mut column = logical.column;
+
+        for line in &self.rows[logical.row] {
+            if column < line.width {
+                break;
+            }
+            column -= line.width;
+            row += 1;
+        }
+
+        Coordinate { row, column }
+    }
+
+    /// maps a byte span in a logical row to the bytes of the physical line it starts on. A span
+    /// that was wrapped runs past the end of that line.
+    pub(crate) fn byte_span_to_physical(&self, row: usize, span: &Range<usize>) -> Range<usize> {
+        let mut start = span.start;
+
+        for line in &self.rows[row] {
+            if start < line.bytes {
+                break;
+            }
+            start -= line.bytes;
+        }
+
+        start..start + span.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(lines: &[&str], width: usize) -> Vec<Result<String, SchematicError>> {
+        let mut line_map = LineMap::new();
+        LogicalLines::new(lines.iter().map(|l| l.to_string()), width, &mut line_map).collect()
+    }
+
+    #[test]
+    fn logical_lines_work() {
+        let rows = rows(&["ab", "cd", "abcd", "a", "bc", "d"], 4);
+        assert_eq!(
+            rows.into_iter().map(Result::unwrap).collect::<Vec<_>>(),
+            vec!["abcd", "abcd", "abcd"]
+        );
+    }
+
+    #[test]
+    fn misaligned_rows_are_rejected() {
+        // "cde" runs past the end of the first row
+        let results = rows(&["ab", "cde", "fgh"], 4);
+        assert_eq!(results.len(), 1);
+        assert!(matches!(
+            results[0],
+            Err(SchematicError::MisalignedRow {
+                line: 1,
+                width: 5,
+                logical_width: 4
+            })
+        ));
+
+        // the last row is cut short
+        let results = rows(&["abcd", "ef"], 4);
+        assert_eq!(results[0].as_deref().unwrap(), "abcd");
+        assert!(matches!(
+            results[1],
+            Err(SchematicError::MisalignedRow {
+                line: 1,
+                width: 2,
+                ..
+            })
+        ));
+    }
+}
@@ -0,0 +1,118 @@
+use std::collections::HashSet;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use day4::{Card, ScoringRule, StandardRules};
+
+const CARDS: u32 = 2_000_000;
+
+/// generates card lines with 10 winning numbers and 25 numbers in 1..=99, like the puzzle input
+fn deck(cards: u32) -> Vec<String> {
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        (state % 99 + 1) as u32
+    };
+
+    (1..=cards)
+        .map(|number| {
+            let winning: Vec<String> = (0..10).map(|_| next().to_string()).collect();
+            let numbers: Vec<String> = (0..25).map(|_| next().to_string()).collect();
+            format!(
+                "Card {}: {} | {}",
+                number,
+                winning.join(" "),
+                numbers.join(" ")
+            )
+        })
+        .collect()
+}
+
+/// a card line split into its winning numbers and numbers, the way cards were stored before
+/// they used bitsets
+fn hashed_card(line: &str) -> (HashSet<u32>, Vec<u32>) {
+    let (_, sides) = line.split_once(':').unwrap();
+    let (winning, numbers) = sides.split_once('|').unwrap();
+
+    (parse(winning).collect(), parse(numbers).collect())
+}
+
+fn parse(side: &str) -> impl Iterator<Item = u32> + '_ {
+    side.split_whitespace().map(|n| n.parse().unwrap())
+}
+
+/// the points of a card with the given number of matches under the standard doubling rule
+fn doubling(matches: usize) -> u64 {
+    if matches == 0 {
+        0
+    } else {
+        1 << (matches - 1)
+    }
+}
+
+/// parses and scores every line, with both representations
+fn scoring(c: &mut Criterion) {
+    let lines = deck(CARDS);
+    let rule = StandardRules::default();
+
+    let mut group = c.benchmark_group("scoring");
+    group.sample_size(10);
+
+    group.bench_function("hashset", |b| {
+        b.iter(|| {
+            black_box(&lines)
+                .iter()
+                .map(|line| {
+                    let (winning, numbers) = hashed_card(line);
+                    doubling(numbers.iter().filter(|n| winning.contains(n)).count())
+                })
+                .sum::<u64>()
+        })
+    });
+
+    group.bench_function("bitset", |b| {
+        b.iter(|| {
+            black_box(&lines)
+                .iter()
+                .filter_map(|line| Card::from_line(line))
+                .map(|card| rule.points(&card).unwrap())
+                .sum::<u64>()
+        })
+    });
+
+    group.finish();
+}
+
+/// both representations are parsed from the same lines up front, so only matching is timed
+fn matching(c: &mut Criterion) {
+    let lines = deck(CARDS);
+    let hashed: Vec<(HashSet<u32>, Vec<u32>)> = lines.iter().map(|l| hashed_card(l)).collect();
+    let cards: Vec<Card> = lines.iter().filter_map(|l| Card::from_line(l)).collect();
+
+    let mut group = c.benchmark_group("matching");
+    group.sample_size(10);
+
+    group.bench_function("hashset", |b| {
+        b.iter(|| {
+            black_box(&hashed)
+                .iter()
+                .map(|(winning, numbers)| numbers.iter().filter(|n| winning.contains(n)).count())
+                .sum::<usize>()
+        })
+    });
+
+    group.bench_function("bitset", |b| {
+        b.iter(|| {
+            black_box(&cards)
+                .iter()
+                .map(|card| card.num_winning() as usize)
+                .sum::<usize>()
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, scoring, matching);
+criterion_main!(benches);
@@ -0,0 +1,104 @@
+use std::fmt;
+
+use num_bigint::BigUint;
+
+/// A count of card copies. The fixed-width counters are checked, so a count that doesn't fit is
+/// reported as an error instead of panicking or wrapping.
+pub trait Counter: Clone + PartialEq + fmt::Debug + fmt::Display {
+    fn zero() -> Self;
+
+    fn one() -> Self;
+
+    /// returns the sum of the counts, or None if it is out of range
+    fn checked_add(&self, other: &Self) -> Option<Self>;
+}
+
+macro_rules! impl_counter {
+    ($($t:ty),*) => {
+        $(
+            impl Counter for $t {
+                fn zero() -> Self {
+                    0
+                }
+
+                fn one() -> Self {
+                    1
+                }
+
+                fn checked_add(&self, other: &Self) -> Option<Self> {
+                    <$t>::checked_add(*self, *other)
+                }
+            }
+        )*
+    };
+}
+
+impl_counter!(u32, u64, u128);
+
+/// Counts that never overflow, however long the chain of copies gets
+impl Counter for BigUint {
+    fn zero() -> Self {
+        BigUint::ZERO
+    }
+
+    fn one() -> Self {
+        BigUint::from(1_u32)
+    }
+
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        Some(self + other)
+    }
+}
+
+/// A count modulo M, for decks too large to count exactly. M must be greater than 0, which is
+/// checked at compile time:
+///
+/// ```compile_fail
+/// use day4::ModCount;
+///
+/// let count = ModCount::<0>::new(1);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModCount<const M: u64>(u64);
+
+impl<const M: u64> ModCount<M> {
+    pub fn new(count: u64) -> Self {
+        const { assert!(M > 0, "the modulus of a ModCount must be greater than 0") };
+        Self(count % M)
+    }
+
+    pub fn value(&self) -> u64 {
+        self.0
+    }
+}
+
+impl<const M: u64> fmt::Display for ModCount<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (mod {})", self.0, M)
+    }
+}
+
+impl<const M: u64> Counter for ModCount<M> {
+    fn zero() -> Self {
+        Self::new(0)
+    }
+
+    fn one() -> Self {
+        Self::new(1)
+    }
+
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        // both counts are below M, so their sum fits in a u128
+        let sum = (u128::from(self.0) + u128::from(other.0)) % u128::from(M);
+        Some(Self(sum as u64))
+    }
+}
+
+/// sums the counts, or returns None if the sum is out of range
+pub(crate) fn checked_sum<'a, C: Counter + 'a>(
+    counts: impl IntoIterator<Item = &'a C>,
+) -> Option<C> {
+    counts
+        .into_iter()
+        .try_fold(C::zero(), |acc, count| acc.checked_add(count))
+}
@@ -0,0 +1,93 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+use crate::{ParseError, TableIssue};
+
+#[derive(Debug)]
+pub enum CardError {
+    Io(io::Error),
+
+    /// card lines that the strict parser rejected
+    Parse(Vec<ParseError>),
+
+    /// the card lines have duplicate or missing cards
+    InvalidTable(Vec<TableIssue>),
+
+    /// a card wins copies of a card that isn't after it, so cards can't be resolved in order
+    BackwardWin {
+        from: u32,
+        to: u32,
+    },
+
+    /// the copies of a card, or the total number of cards when there is no card, don't fit in the
+    /// counter
+    CountOverflow {
+        card: Option<u32>,
+    },
+
+    /// the points of a card, or the total points when there is no card, don't fit in a u64
+    PointsOverflow {
+        card: Option<u32>,
+    },
+
+    /// a deck can't be dealt from the simulation settings
+    InvalidDeck(String),
+
+    /// the cards win copies of each other in a loop, so they can't be resolved in any order
+    Cycle(Vec<u32>),
+}
+
+impl fmt::Display for CardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CardError::Io(e) => write!(f, "failed to read cards: {}", e),
+            CardError::Parse(errors) => {
+                write!(f, "malformed cards:")?;
+                for error in errors {
+                    write!(f, "\n  {}", error)?;
+                }
+                Ok(())
+            }
+            CardError::InvalidTable(issues) => {
+                write!(f, "invalid cards:")?;
+                for issue in issues {
+                    write!(f, "\n  {}", issue)?;
+                }
+                Ok(())
+            }
+            CardError::BackwardWin { from, to } => {
+                write!(f, "card {} wins copies of earlier card {}", from, to)
+            }
+            CardError::CountOverflow { card: Some(card) } => {
+                write!(f, "the number of copies of card {} is out of range", card)
+            }
+            CardError::CountOverflow { card: None } => {
+                write!(f, "the total number of cards is out of range")
+            }
+            CardError::PointsOverflow { card: Some(card) } => {
+                write!(f, "the points of card {} are out of range", card)
+            }
+            CardError::PointsOverflow { card: None } => {
+                write!(f, "the total points are out of range")
+            }
+            CardError::InvalidDeck(reason) => write!(f, "invalid deck: {}", reason),
+            CardError::Cycle(cards) => write!(f, "cards {:?} win copies of each other", cards),
+        }
+    }
+}
+
+impl Error for CardError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CardError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for CardError {
+    fn from(e: io::Error) -> Self {
+        CardError::Io(e)
+    }
+}
@@ -1,9 +1,34 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashSet};
 use std::fs::File;
 use std::io;
 use std::io::{BufRead, BufReader};
 
-pub fn pt1(path: &str) -> Result<u32, io::Error> {
+mod counter;
+mod error;
+mod numbers;
+mod parse;
+mod report;
+mod rules;
+mod simulate;
+mod stream;
+mod table;
+
+pub use counter::{Counter, ModCount};
+pub use error::CardError;
+pub use numbers::NumberSet;
+pub use parse::{DuplicateNumber, ParseError, ParseErrorKind, Side, StrictParser};
+pub use report::{card_reports, CardReport, ReportFormat};
+pub use rules::{Copies, Points, ScoringRule, StandardRules};
+pub use simulate::{simulate, DeckConfig, SimulationReport, Summary};
+pub use stream::{CardStream, RunningTotal};
+pub use table::{CardTable, Resolution, TableIssue};
+
+pub fn pt1(path: &str) -> Result<u64, CardError> {
+    pt1_with_rule(path, &StandardRules::default())
+}
+
+/// sums the points of every card, scored by the rule
+pub fn pt1_with_rule<R: ScoringRule + ?Sized>(path: &str, rule: &R) -> Result<u64, CardError> {
     let file = File::open(path)?;
 
     let buffered = BufReader::new(file);
@@ -11,61 +36,102 @@ pub fn pt1(path: &str) -> Result<u32, io::Error> {
     // create an iterator over the lines of the file
     let lines_iter = buffered.lines().filter_map(|line_result| line_result.ok());
 
-    let mut total_score: u32 = 0;
+    let mut total_score: u64 = 0;
 
     for line in lines_iter {
         if let Some(card) = Card::from_line(&line) {
-            total_score += card.points();
+            let points = rule.points(&card).ok_or(CardError::PointsOverflow {
+                card: Some(card.number()),
+            })?;
+            total_score = total_score
+                .checked_add(points)
+                .ok_or(CardError::PointsOverflow { card: None })?;
         };
     }
 
     return Ok(total_score);
 }
 
-pub fn pt2(path: &str) -> Result<u32, io::Error> {
+pub fn pt2(path: &str) -> Result<u64, CardError> {
+    pt2_with_rule(path, &StandardRules::default(), Resolution::Ascending)
+}
+
+/// counts the cards held at the end, with copies won by the rule and the cards resolved in the
+/// given order
+pub fn pt2_with_rule<R: ScoringRule + ?Sized>(
+    path: &str,
+    rule: &R,
+    resolution: Resolution,
+) -> Result<u64, CardError> {
+    pt2_counted(path, rule, resolution)
+}
+
+/// counts the cards held at the end in the given counter type, for decks whose counts don't fit
+/// in a u64
+pub fn pt2_counted<C: Counter, R: ScoringRule + ?Sized>(
+    path: &str,
+    rule: &R,
+    resolution: Resolution,
+) -> Result<C, CardError> {
+    let table = read_table(path)?;
+
+    let copies: BTreeMap<u32, C> = table.copies(rule, resolution)?;
+
+    counter::checked_sum(copies.values()).ok_or(CardError::CountOverflow { card: None })
+}
+
+/// counts the cards held at the end like pt2, rejecting the input if any card line is malformed
+pub fn pt2_strict(path: &str) -> Result<u64, CardError> {
     let file = File::open(path)?;
 
     let buffered = BufReader::new(file);
 
     // create an iterator over the lines of the file
-    let lines_iter = buffered.lines().filter_map(|line_result| line_result.ok());
+    let lines_iter = buffered.lines().map_while(Result::ok);
 
-    let mut card_frequencies: HashMap<u32, u32> = HashMap::new();
+    let table = CardTable::from_lines_strict(lines_iter)?;
 
-    // the largest card number, assumes card numbers go up line-by-line
-    let mut last_card_number = 0;
+    let copies: BTreeMap<u32, u64> =
+        table.copies(&StandardRules::default(), Resolution::Ascending)?;
 
-    for line in lines_iter {
-        if let Some(card) = Card::from_line(&line) {
-            // increment frequency of the current card
-            let num_cards = card_frequencies.get(&card.number).unwrap_or(&0) + 1;
+    counter::checked_sum(copies.values()).ok_or(CardError::CountOverflow { card: None })
+}
 
-            card_frequencies.insert(card.number, num_cards);
+/// counts the cards held at the end like pt2, reading the deck as a stream in constant memory.
+/// Cards must be numbered in order without gaps.
+pub fn pt2_streaming<B: BufRead>(reader: B) -> Result<u64, CardError> {
+    let mut total = 0;
 
-            let num_winning = card.num_winning();
+    for running in CardStream::new(reader) {
+        total = running?.total;
+    }
 
-            // increase frequency of cards following the current card
-            // by the frequency of the current card
-            for n in (card.number + 1)..(card.number + 1 + num_winning) {
-                let count = card_frequencies.entry(n).or_insert(0);
-                *count += num_cards;
-            }
+    Ok(total)
+}
 
-            last_card_number = card.number;
-        };
-    }
+/// lists every card with its matches, points and copies, for comparing answers card by card
+pub fn report<R: ScoringRule + ?Sized>(
+    path: &str,
+    rule: &R,
+    resolution: Resolution,
+    format: ReportFormat,
+) -> Result<String, CardError> {
+    let table = read_table(path)?;
 
-    let mut num_cards = 0;
+    let reports = card_reports(&table, rule, resolution)?;
 
-    // find the total number of cards
-    for (k, v) in card_frequencies {
-        // exclude card numbers that don't actually exist
-        if k <= last_card_number {
-            num_cards += v;
-        }
-    }
+    Ok(report::render(&reports, format))
+}
+
+fn read_table(path: &str) -> Result<CardTable, io::Error> {
+    let file = File::open(path)?;
+
+    let buffered = BufReader::new(file);
 
-    return Ok(num_cards);
+    // create an iterator over the lines of the file
+    let lines_iter = buffered.lines().map_while(Result::ok);
+
+    Ok(CardTable::from_lines(lines_iter))
 }
 
 fn chars_to_numbers(chars: &str, digits: &mut Vec<u32>) -> Vec<u32> {
@@ -100,15 +166,37 @@ fn to_num(digits: &[u32]) -> u32 {
 }
 
 #[derive(PartialEq, Debug)]
-struct Card {
-    winning_numbers: HashSet<u32>,
-    numbers: Vec<u32>,
+pub struct Card {
+    winning_numbers: NumberSet,
+    numbers: NumberSet,
+    /// owned numbers written again after their first appearance, once per extra appearance.
+    /// Each appearance of an owned number counts as a match, so these count on top of `numbers`.
+    repeats: Vec<u32>,
     /// the card number
     number: u32,
 }
 
 impl Card {
-    fn from_line(line: &str) -> Option<Self> {
+    /// a card from its number, winning numbers and owned numbers as written. A repeated winning
+    /// number only counts once, but a repeated owned number matches every time it appears.
+    pub(crate) fn new(number: u32, winning_numbers: &[u32], numbers: &[u32]) -> Self {
+        let mut seen = HashSet::new();
+        let repeats = numbers
+            .iter()
+            .copied()
+            .filter(|&n| !seen.insert(n))
+            .collect();
+
+        Self {
+            winning_numbers: NumberSet::from_numbers(winning_numbers),
+            numbers: NumberSet::from_numbers(numbers),
+            repeats,
+            number,
+        }
+    }
+
+    /// parses a card line such as "Card 1: 41 48 | 83 41", or returns None if it isn't a card
+    pub fn from_line(line: &str) -> Option<Self> {
         let mut splits = line.split(':');
 
         // parse out the card number
@@ -121,73 +209,92 @@ impl Card {
             }
         }
 
-        let mut card = Self {
-            winning_numbers: HashSet::new(),
-            numbers: Vec::new(),
-            number: to_num(&card_label_digits),
-        };
+        let number = to_num(&card_label_digits);
 
         let mut x = splits.next()?.split('|');
         let winning_chars = x.next()?;
 
         let mut digits: Vec<u32> = Vec::new();
 
-        card.winning_numbers =
-            HashSet::from_iter(chars_to_numbers(winning_chars, &mut digits).into_iter());
+        let winning_numbers = chars_to_numbers(winning_chars, &mut digits);
 
         let num_chars = x.next()?;
 
-        card.numbers = chars_to_numbers(num_chars, &mut digits);
+        let numbers = chars_to_numbers(num_chars, &mut digits);
 
-        return Some(card);
+        return Some(Self::new(number, &winning_numbers, &numbers));
     }
 
-    /// returns number of winning numbers on the card
-    fn num_winning(&self) -> u32 {
-        let mut num_winning = 0;
+    pub fn number(&self) -> u32 {
+        self.number
+    }
 
-        for n in self.numbers.iter() {
-            if self.winning_numbers.contains(&n) {
-                num_winning += 1;
-            }
-        }
-        return num_winning;
+    /// iterates over the numbers on the card that are also winning numbers, in ascending order.
+    /// A repeated owned number is yielded once per appearance.
+    pub fn matching_numbers(&self) -> impl Iterator<Item = u32> + '_ {
+        let mut matching: Vec<u32> = self.numbers.common(&self.winning_numbers).collect();
+        matching.extend(self.repeated_matches());
+        matching.sort_unstable();
+        matching.into_iter()
+    }
+
+    /// returns number of winning numbers on the card, counting a repeated owned number once per
+    /// appearance
+    pub fn num_winning(&self) -> u32 {
+        let repeated = self.repeated_matches().count() as u32;
+        self.numbers.count_common(&self.winning_numbers) + repeated
     }
 
-    fn points(&self) -> u32 {
-        // track count of winning cards
-        let num_winning = self.num_winning();
+    /// the extra appearances of owned numbers that are winning numbers
+    fn repeated_matches(&self) -> impl Iterator<Item = u32> + '_ {
+        self.repeats
+            .iter()
+            .copied()
+            .filter(|&n| self.winning_numbers.contains(n))
+    }
 
-        if num_winning == 0 {
-            return 0;
-        } else {
-            return 2_u32.pow(num_winning - 1);
-        }
+    /// the points of the card under the standard rules, or None if they don't fit in a u64
+    pub fn points(&self) -> Option<u64> {
+        Points::Doubling.score(self.num_winning())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use num_bigint::BigUint;
 
     #[test]
     fn line_to_card_works() {
         let result = Card::from_line("Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53");
-        let winning_numbers = HashSet::from_iter([41, 48, 83, 86, 17].into_iter());
+        let winning_numbers = NumberSet::from_numbers(&[41, 48, 83, 86, 17]);
         assert_eq!(
             result,
             Some(Card {
                 number: 1,
                 winning_numbers,
-                numbers: vec![83, 86, 6, 31, 17, 9, 48, 53]
+                numbers: NumberSet::from_numbers(&[83, 86, 6, 31, 17, 9, 48, 53]),
+                repeats: Vec::new(),
             })
         );
     }
 
+    #[test]
+    fn repeated_numbers_work() {
+        // a repeated owned number matches every time it appears, a repeated winning number once
+        let card = Card::from_line("Card 1: 5 7 7 | 5 5 7 9 9").unwrap();
+        assert_eq!(card.num_winning(), 3);
+        assert_eq!(card.matching_numbers().collect::<Vec<_>>(), vec![5, 5, 7]);
+        assert_eq!(card.points(), Some(4));
+
+        let card = Card::from_line("Card 2: 5 | 9 9").unwrap();
+        assert_eq!(card.num_winning(), 0);
+    }
+
     #[test]
     fn score_works() {
         let card = Card::from_line("Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53").unwrap();
-        assert_eq!(8, card.points());
+        assert_eq!(Some(8), card.points());
     }
 
     #[test]
@@ -196,9 +303,178 @@ mod tests {
         assert_eq!(18519, pt1("input").unwrap());
     }
 
+    #[test]
+    fn card_table_works() {
+        let lines = [
+            "Card 2: 13 32 20 16 61 | 61 30 68 82 17 32 24 19",
+            "Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53",
+            "Card 4: 41 92 73 84 69 | 59 84 76 51 58  5 54 83",
+            "Card 3:  1 21 53 59 44 | 69 82 63 72 16 21 14  1",
+        ];
+        let table = CardTable::from_lines(lines.iter().map(|l| l.to_string()));
+
+        // out of order lines are reported, but still counted correctly
+        assert_eq!(
+            table.issues(),
+            &[
+                TableIssue::OutOfOrder { number: 1, line: 2 },
+                TableIssue::OutOfOrder { number: 3, line: 4 }
+            ]
+        );
+        let rule = StandardRules::default();
+        let copies: BTreeMap<u32, u32> = table.copies(&rule, Resolution::Ascending).unwrap();
+        assert_eq!(
+            copies.into_iter().collect::<Vec<_>>(),
+            vec![(1, 1), (2, 2), (3, 4), (4, 8)]
+        );
+
+        // both resolutions agree when every card wins copies of later cards
+        assert_eq!(
+            table
+                .copies::<u32, _>(&rule, Resolution::Topological)
+                .unwrap(),
+            table
+                .copies::<u32, _>(&rule, Resolution::Ascending)
+                .unwrap()
+        );
+
+        let lines = [
+            "Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53",
+            "Card 1: 13 32 20 16 61 | 61 30 68 82 17 32 24 19",
+            "Card 4: 41 92 73 84 69 | 59 84 76 51 58  5 54 83",
+        ];
+        let table = CardTable::from_lines(lines.iter().map(|l| l.to_string()));
+        let expected_issues = vec![
+            TableIssue::Duplicate { number: 1, line: 2 },
+            TableIssue::Missing { numbers: 2..4 },
+        ];
+        assert_eq!(table.issues(), expected_issues.as_slice());
+        assert_eq!(expected_issues[1].to_string(), "cards 2 to 3 are missing");
+        assert!(matches!(
+            table.copies::<u32, _>(&rule, Resolution::Ascending),
+            Err(CardError::InvalidTable(issues)) if issues == expected_issues
+        ));
+
+        // a huge gap is a single issue
+        let lines = ["Card 1: 1 | 2", "Card 4000000000: 1 | 2"];
+        let table = CardTable::from_lines(lines.iter().map(|l| l.to_string()));
+        assert_eq!(
+            table.issues(),
+            &[TableIssue::Missing {
+                numbers: 2..4_000_000_000
+            }]
+        );
+    }
+
     #[test]
     fn pt2_works() {
         assert_eq!(30, pt2("test_input").unwrap());
+        assert_eq!(30, pt2_strict("test_input").unwrap());
+        assert_eq!(
+            11787590,
+            pt2_streaming(BufReader::new(File::open("input").unwrap())).unwrap()
+        );
         assert_eq!(11787590, pt2("input").unwrap());
+        assert_eq!(
+            11787590,
+            pt2_with_rule("input", &StandardRules::default(), Resolution::Topological).unwrap()
+        );
+    }
+
+    #[test]
+    fn counters_work() {
+        // every card wins copies of the next 10 cards, so the counts grow past a u64
+        let lines =
+            (1..=100).map(|n| format!("Card {}: 1 2 3 4 5 6 7 8 9 10 | 1 2 3 4 5 6 7 8 9 10", n));
+        let table = CardTable::from_lines(lines);
+        let rule = StandardRules::default();
+
+        assert!(matches!(
+            table.copies::<u64, _>(&rule, Resolution::Ascending),
+            Err(CardError::CountOverflow { card: Some(_) })
+        ));
+
+        let exact: BTreeMap<u32, u128> = table.copies(&rule, Resolution::Ascending).unwrap();
+        let big: BTreeMap<u32, BigUint> = table.copies(&rule, Resolution::Ascending).unwrap();
+        let modular: BTreeMap<u32, ModCount<1_000_000_007>> =
+            table.copies(&rule, Resolution::Ascending).unwrap();
+
+        for (number, count) in exact {
+            assert_eq!(big[&number], BigUint::from(count));
+            assert_eq!(modular[&number].value() as u128, count % 1_000_000_007);
+        }
+    }
+
+    #[test]
+    fn strict_table_works() {
+        let lines = [
+            "Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53",
+            "Card 1a2: 13 32 20 16 61 | 61 30 68 82 17 32 24 19",
+            "",
+            "Card 3:  1 21 53 59 | 69 82 63 72 16 21 14  1",
+        ];
+        let result = CardTable::from_lines_strict(lines.iter().map(|l| l.to_string()));
+        let Err(CardError::Parse(errors)) = result else {
+            panic!("expected parse errors, got {:?}", result);
+        };
+        assert_eq!(
+            errors
+                .iter()
+                .map(|e| (e.line, e.column))
+                .collect::<Vec<_>>(),
+            vec![(2, 7), (4, 1)]
+        );
+
+        let lines = [
+            "Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53",
+            "Card 2: 13 32 20 16 61 | 61 30 68 82 17 32 32 19",
+        ];
+        let table = CardTable::from_lines_strict(lines.iter().map(|l| l.to_string())).unwrap();
+        assert_eq!(
+            table.issues(),
+            &[TableIssue::DuplicateNumber {
+                number: 32,
+                side: Side::Owned,
+                line: 2,
+                column: 44
+            }]
+        );
+        assert_eq!(table.cards().count(), 2);
+    }
+
+    #[test]
+    fn report_works() {
+        let rule = StandardRules::default();
+        let csv = report("input", &rule, Resolution::Ascending, ReportFormat::Csv).unwrap();
+
+        // the copies column adds up to the pt2 answer
+        let copies: u64 = csv
+            .lines()
+            .skip(1)
+            .map(|line| line.split(',').nth(4).unwrap().parse::<u64>().unwrap())
+            .sum();
+        assert_eq!(copies, pt2("input").unwrap());
+    }
+
+    #[test]
+    fn rules_work() {
+        let rule = StandardRules {
+            points: Points::Linear,
+            copies: Copies::PreviousN,
+        };
+        // test_input matches are 4, 2, 2, 1, 0, 0
+        assert_eq!(9, pt1_with_rule("test_input", &rule).unwrap());
+
+        // winning copies of earlier cards can only be resolved topologically
+        assert!(matches!(
+            pt2_with_rule("test_input", &rule, Resolution::Ascending),
+            Err(CardError::BackwardWin { from: 2, to: 1 })
+        ));
+        // card 4 wins a copy of 3, 3 wins copies of 2 and 1, and 2 wins a copy of 1, so card 4
+        // ends with 1 copy, 3 with 2, 2 with 3 and 1 with 6
+        assert_eq!(
+            14,
+            pt2_with_rule("test_input", &rule, Resolution::Topological).unwrap()
+        );
     }
 }
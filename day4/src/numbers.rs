@@ -0,0 +1,159 @@
+use std::cmp::Ordering;
+
+/// The largest number that fits in the bitset representation
+const BITSET_MAX: u32 = 127;
+
+/// A set of the numbers on one side of a card. Sets of small numbers are stored as a bitset so
+/// that counting matches is a popcount, wider ranges fall back to a sorted vec.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NumberSet {
+    /// bit n of the 128 bits is set if n is in the set
+    Bits([u64; 2]),
+
+    /// the numbers in ascending order, without duplicates
+    Sorted(Vec<u32>),
+}
+
+impl NumberSet {
+    pub fn from_numbers(numbers: &[u32]) -> Self {
+        if numbers.iter().all(|&n| n <= BITSET_MAX) {
+            let mut bits = [0; 2];
+            for &n in numbers {
+                bits[(n / 64) as usize] |= 1 << (n % 64);
+            }
+            NumberSet::Bits(bits)
+        } else {
+            let mut sorted = numbers.to_vec();
+            sorted.sort_unstable();
+            sorted.dedup();
+            NumberSet::Sorted(sorted)
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            NumberSet::Bits(bits) => bits.iter().map(|w| w.count_ones() as usize).sum(),
+            NumberSet::Sorted(numbers) => numbers.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn contains(&self, number: u32) -> bool {
+        match self {
+            NumberSet::Bits(bits) => {
+                number <= BITSET_MAX && bits[(number / 64) as usize] & (1 << (number % 64)) != 0
+            }
+            NumberSet::Sorted(numbers) => numbers.binary_search(&number).is_ok(),
+        }
+    }
+
+    /// iterates over the numbers in ascending order
+    pub fn iter(&self) -> Box<dyn Iterator<Item = u32> + '_> {
+        match self {
+            NumberSet::Bits(bits) => Box::new(bit_numbers(*bits)),
+            NumberSet::Sorted(numbers) => Box::new(numbers.iter().copied()),
+        }
+    }
+
+    /// counts the numbers in both sets
+    pub fn count_common(&self, other: &NumberSet) -> u32 {
+        match (self, other) {
+            (NumberSet::Bits(a), NumberSet::Bits(b)) => {
+                (a[0] & b[0]).count_ones() + (a[1] & b[1]).count_ones()
+            }
+            _ => self.common(other).count() as u32,
+        }
+    }
+
+    /// iterates over the numbers in both sets in ascending order
+    pub fn common<'a>(&'a self, other: &'a NumberSet) -> Box<dyn Iterator<Item = u32> + 'a> {
+        match (self, other) {
+            (NumberSet::Bits(a), NumberSet::Bits(b)) => {
+                Box::new(bit_numbers([a[0] & b[0], a[1] & b[1]]))
+            }
+            _ => Box::new(SortedIntersection {
+                a: self.iter().peekable(),
+                b: other.iter().peekable(),
+            }),
+        }
+    }
+}
+
+/// iterates over the set bits of a bitset in ascending order
+fn bit_numbers(bits: [u64; 2]) -> impl Iterator<Item = u32> {
+    bits.into_iter()
+        .enumerate()
+        .flat_map(|(word_idx, mut word)| {
+            std::iter::from_fn(move || {
+                if word == 0 {
+                    return None;
+                }
+                let bit = word.trailing_zeros();
+                word &= word - 1;
+                Some(word_idx as u32 * 64 + bit)
+            })
+        })
+}
+
+/// Walks two ascending iterators together, yielding the numbers found in both
+struct SortedIntersection<A: Iterator<Item = u32>, B: Iterator<Item = u32>> {
+    a: std::iter::Peekable<A>,
+    b: std::iter::Peekable<B>,
+}
+
+impl<A: Iterator<Item = u32>, B: Iterator<Item = u32>> Iterator for SortedIntersection<A, B> {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<u32> {
+        loop {
+            let (a, b) = (*self.a.peek()?, *self.b.peek()?);
+            match a.cmp(&b) {
+                Ordering::Less => {
+                    self.a.next();
+                }
+                Ordering::Greater => {
+                    self.b.next();
+                }
+                Ordering::Equal => {
+                    self.a.next();
+                    self.b.next();
+                    return Some(a);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn number_set_works() {
+        let small = NumberSet::from_numbers(&[41, 48, 83, 86, 17, 0, 127]);
+        assert!(matches!(small, NumberSet::Bits(_)));
+        assert_eq!(small.len(), 7);
+        assert!(small.contains(127) && small.contains(0) && !small.contains(128));
+        assert_eq!(
+            small.iter().collect::<Vec<_>>(),
+            vec![0, 17, 41, 48, 83, 86, 127]
+        );
+
+        let wide = NumberSet::from_numbers(&[500, 86, 17, 17, 9]);
+        assert_eq!(wide, NumberSet::Sorted(vec![9, 17, 86, 500]));
+
+        let other = NumberSet::from_numbers(&[83, 86, 6, 31, 17, 9, 48, 53]);
+        assert_eq!(small.count_common(&other), 4);
+        assert_eq!(
+            small.common(&other).collect::<Vec<_>>(),
+            vec![17, 48, 83, 86]
+        );
+
+        // the bitset and sorted paths agree
+        assert_eq!(wide.count_common(&other), 3);
+        assert_eq!(other.common(&wide).collect::<Vec<_>>(), vec![9, 17, 86]);
+    }
+}
@@ -0,0 +1,278 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::Card;
+
+/// A card line that couldn't be parsed. Lines and columns start from 1, and columns count chars.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: ParseErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseErrorKind {
+    /// the line doesn't start with "Card" followed by whitespace and the card number
+    MalformedLabel,
+
+    /// the line is missing the ':' after the label or the '|' between the two sides
+    MissingSeparator(char),
+
+    /// the line has more than one '|'
+    ExtraSeparator,
+
+    /// something other than a number, or a number too large for a u32
+    InvalidNumber(String),
+
+    /// the card has a different count of winning or owned numbers than the first card
+    CountMismatch {
+        expected: (usize, usize),
+        found: (usize, usize),
+    },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}: ", self.line, self.column)?;
+
+        match &self.kind {
+            ParseErrorKind::MalformedLabel => write!(f, "expected a label like \"Card 1:\""),
+            ParseErrorKind::MissingSeparator(c) => write!(f, "missing '{}'", c),
+            ParseErrorKind::ExtraSeparator => write!(f, "more than one '|'"),
+            ParseErrorKind::InvalidNumber(token) => write!(f, "invalid number \"{}\"", token),
+            ParseErrorKind::CountMismatch { expected, found } => write!(
+                f,
+                "expected {} winning and {} owned numbers, found {} and {}",
+                expected.0, expected.1, found.0, found.1
+            ),
+        }
+    }
+}
+
+/// The side of a card a number is on
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Side {
+    Winning,
+    Owned,
+}
+
+/// A number that appears more than once on the same side of a card
+#[derive(Debug, Clone, PartialEq)]
+pub struct DuplicateNumber {
+    pub number: u32,
+    pub side: Side,
+    pub column: usize,
+}
+
+/// Parses card lines strictly, checking that every card has the same count of winning and owned
+/// numbers as the first
+#[derive(Debug, Default)]
+pub struct StrictParser {
+    /// the counts of winning and owned numbers on the first card
+    counts: Option<(usize, usize)>,
+}
+
+impl StrictParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// parses a line such as "Card 1: 41 48 | 83 41", returning the card along with any duplicate
+    /// numbers on it. Line numbers start from 1.
+    pub fn parse_line(
+        &mut self,
+        line: &str,
+        line_number: usize,
+    ) -> Result<(Card, Vec<DuplicateNumber>), ParseError> {
+        let error = |byte: usize, kind| ParseError {
+            line: line_number,
+            column: column(line, byte),
+            kind,
+        };
+
+        let colon = line
+            .find(':')
+            .ok_or_else(|| error(line.len(), ParseErrorKind::MissingSeparator(':')))?;
+        let number = parse_label(&line[..colon])
+            .map_err(|byte| error(byte, ParseErrorKind::MalformedLabel))?;
+
+        let body_start = colon + 1;
+        let mut sides = line[body_start..].split('|');
+        let winning = sides.next().unwrap_or_default();
+        let Some(owned) = sides.next() else {
+            return Err(error(line.len(), ParseErrorKind::MissingSeparator('|')));
+        };
+        let owned_start = body_start + winning.len() + 1;
+        if sides.next().is_some() {
+            let byte = owned_start + owned.len();
+            return Err(error(byte, ParseErrorKind::ExtraSeparator));
+        }
+
+        let mut duplicates = Vec::new();
+        let mut parse_side = |text: &str, start: usize, side: Side| {
+            let mut seen = HashSet::new();
+            let mut numbers = Vec::new();
+
+            for (offset, token) in tokens(text) {
+                let byte = start + offset;
+                let number = parse_number(token)
+                    .ok_or_else(|| error(byte, ParseErrorKind::InvalidNumber(token.to_string())))?;
+                if !seen.insert(number) {
+                    duplicates.push(DuplicateNumber {
+                        number,
+                        side,
+                        column: column(line, byte),
+                    });
+                }
+                numbers.push(number);
+            }
+
+            Ok(numbers)
+        };
+        let winning_numbers = parse_side(winning, body_start, Side::Winning)?;
+        let numbers = parse_side(owned, owned_start, Side::Owned)?;
+
+        let found = (winning_numbers.len(), numbers.len());
+        let expected = *self.counts.get_or_insert(found);
+        if found != expected {
+            return Err(error(0, ParseErrorKind::CountMismatch { expected, found }));
+        }
+
+        let card = Card::new(number, &winning_numbers, &numbers);
+
+        Ok((card, duplicates))
+    }
+}
+
+/// the 1-based char column of a byte offset in the line
+fn column(line: &str, byte: usize) -> usize {
+    line[..byte].chars().count() + 1
+}
+
+/// parses "Card" followed by whitespace and a number, or returns the byte offset of the first
+/// thing that is wrong with the label
+fn parse_label(label: &str) -> Result<u32, usize> {
+    let rest = label.strip_prefix("Card").ok_or(0_usize)?;
+    let digits = rest.trim_start();
+    let digits_start = label.len() - digits.len();
+
+    if digits_start == "Card".len() {
+        return Err(digits_start);
+    }
+    if let Some(bad) = digits.find(|c: char| !c.is_ascii_digit()) {
+        return Err(digits_start + bad);
+    }
+
+    parse_number(digits).ok_or(digits_start)
+}
+
+/// parses a token of ASCII digits that fits in a u32
+fn parse_number(token: &str) -> Option<u32> {
+    if token.is_empty() || !token.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    token.parse().ok()
+}
+
+/// splits text on spaces, keeping the byte offset of each token
+fn tokens(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    text.split(' ')
+        .scan(0, |offset, token| {
+            let start = *offset;
+            *offset += token.len() + 1;
+            Some((start, token))
+        })
+        .filter(|(_, token)| !token.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(line: &str) -> Result<(Card, Vec<DuplicateNumber>), ParseError> {
+        StrictParser::new().parse_line(line, 1)
+    }
+
+    fn error_at(line: &str) -> (usize, ParseErrorKind) {
+        let error = parse(line).unwrap_err();
+        (error.column, error.kind)
+    }
+
+    #[test]
+    fn strict_parse_works() {
+        let line = "Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53";
+        let (card, duplicates) = parse(line).unwrap();
+        assert_eq!(Some(card), Card::from_line(line));
+        assert!(duplicates.is_empty());
+
+        let (card, duplicates) = parse("Card  12: 41 48 41 | 83 86 86").unwrap();
+        assert_eq!(card.number(), 12);
+        assert_eq!(
+            duplicates,
+            vec![
+                DuplicateNumber {
+                    number: 41,
+                    side: Side::Winning,
+                    column: 17
+                },
+                DuplicateNumber {
+                    number: 86,
+                    side: Side::Owned,
+                    column: 28
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn strict_parse_errors() {
+        assert_eq!(
+            error_at("Card 1a2: 1 | 2"),
+            (7, ParseErrorKind::MalformedLabel)
+        );
+        assert_eq!(
+            error_at("Card1: 1 | 2"),
+            (5, ParseErrorKind::MalformedLabel)
+        );
+        assert_eq!(
+            error_at("Cord 1: 1 | 2"),
+            (1, ParseErrorKind::MalformedLabel)
+        );
+        assert_eq!(
+            error_at("Card 1 1 | 2"),
+            (13, ParseErrorKind::MissingSeparator(':'))
+        );
+        assert_eq!(
+            error_at("Card 1: 1 2"),
+            (12, ParseErrorKind::MissingSeparator('|'))
+        );
+        assert_eq!(
+            error_at("Card 1: 1 | 2 | 3"),
+            (15, ParseErrorKind::ExtraSeparator)
+        );
+        assert_eq!(
+            error_at("Card 1: 1 | 2,3"),
+            (13, ParseErrorKind::InvalidNumber("2,3".to_string()))
+        );
+        assert_eq!(
+            error_at("Card 1: 99999999999 | 2"),
+            (9, ParseErrorKind::InvalidNumber("99999999999".to_string()))
+        );
+
+        let mut parser = StrictParser::new();
+        parser.parse_line("Card 1: 1 2 | 3 4 5", 1).unwrap();
+        let error = parser.parse_line("Card 2: 1 2 | 3 4", 2).unwrap_err();
+        assert_eq!(
+            error,
+            ParseError {
+                line: 2,
+                column: 1,
+                kind: ParseErrorKind::CountMismatch {
+                    expected: (2, 3),
+                    found: (2, 2)
+                }
+            }
+        );
+    }
+}
@@ -0,0 +1,229 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::{CardError, CardTable, Resolution, ScoringRule};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReportFormat {
+    /// an aligned plain text table
+    Table,
+    Csv,
+    Json,
+}
+
+/// The breakdown of how one card was scored
+#[derive(Debug, Clone, PartialEq)]
+pub struct CardReport {
+    pub number: u32,
+
+    /// the numbers on the card that are also winning numbers
+    pub matching_numbers: Vec<u32>,
+
+    pub points: u64,
+
+    /// the copies held once every card has been resolved, including the original
+    pub copies: u64,
+
+    /// the cards that won copies of this card, with the copies each of them handed on
+    pub won_from: Vec<(u32, u64)>,
+}
+
+/// builds a report for every card in the table, in card number order
+pub fn card_reports<R: ScoringRule + ?Sized>(
+    table: &CardTable,
+    rule: &R,
+    resolution: Resolution,
+) -> Result<Vec<CardReport>, CardError> {
+    let copies: BTreeMap<u32, u64> = table.copies(rule, resolution)?;
+
+    // every card hands each card it wins one copy per copy of itself
+    let mut won_from: BTreeMap<u32, Vec<(u32, u64)>> = BTreeMap::new();
+    for card in table.cards() {
+        for won in table.wins(rule, card) {
+            won_from
+                .entry(won)
+                .or_default()
+                .push((card.number(), copies[&card.number()]));
+        }
+    }
+
+    table
+        .cards()
+        .map(|card| {
+            Ok(CardReport {
+                number: card.number(),
+                matching_numbers: card.matching_numbers().collect(),
+                points: rule.points(card).ok_or(CardError::PointsOverflow {
+                    card: Some(card.number()),
+                })?,
+                copies: copies[&card.number()],
+                won_from: won_from.remove(&card.number()).unwrap_or_default(),
+            })
+        })
+        .collect()
+}
+
+/// renders the reports in the format
+pub fn render(reports: &[CardReport], format: ReportFormat) -> String {
+    match format {
+        ReportFormat::Table => render_table(reports),
+        ReportFormat::Csv => render_csv(reports),
+        ReportFormat::Json => render_json(reports),
+    }
+}
+
+const HEADERS: [&str; 6] = ["card", "matches", "numbers", "points", "copies", "won from"];
+
+/// the columns of a report, as text
+fn columns(report: &CardReport) -> [String; 6] {
+    let numbers: Vec<String> = report
+        .matching_numbers
+        .iter()
+        .map(|n| n.to_string())
+        .collect();
+    let won_from: Vec<String> = report
+        .won_from
+        .iter()
+        .map(|(card, copies)| format!("{}x{}", card, copies))
+        .collect();
+
+    [
+        report.number.to_string(),
+        report.matching_numbers.len().to_string(),
+        numbers.join(" "),
+        report.points.to_string(),
+        report.copies.to_string(),
+        won_from.join(" "),
+    ]
+}
+
+fn render_table(reports: &[CardReport]) -> String {
+    let rows: Vec<[String; 6]> = reports.iter().map(columns).collect();
+
+    let mut widths = HEADERS.map(str::len);
+    for row in &rows {
+        for (width, column) in widths.iter_mut().zip(row) {
+            *width = (*width).max(column.len());
+        }
+    }
+
+    let mut out = String::new();
+    let headers = HEADERS.map(String::from);
+
+    for row in std::iter::once(&headers).chain(&rows) {
+        let mut line = String::new();
+        for (i, column) in row.iter().enumerate() {
+            // the number lists are left aligned, every other column is a single number
+            if i == 2 || i == 5 {
+                write!(line, "{:<width$}  ", column, width = widths[i]).unwrap();
+            } else {
+                write!(line, "{:>width$}  ", column, width = widths[i]).unwrap();
+            }
+        }
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+
+    out
+}
+
+fn render_csv(reports: &[CardReport]) -> String {
+    let mut out = HEADERS.join(",").replace(' ', "_");
+    out.push('\n');
+
+    for report in reports {
+        out.push_str(&columns(report).join(","));
+        out.push('\n');
+    }
+
+    out
+}
+
+fn render_json(reports: &[CardReport]) -> String {
+    let cards: Vec<String> = reports
+        .iter()
+        .map(|report| {
+            let numbers: Vec<String> = report
+                .matching_numbers
+                .iter()
+                .map(|n| n.to_string())
+                .collect();
+            let won_from: Vec<String> = report
+                .won_from
+                .iter()
+                .map(|(card, copies)| format!("{{\"card\":{},\"copies\":{}}}", card, copies))
+                .collect();
+
+            format!(
+                "  {{\"card\":{},\"matching_numbers\":[{}],\"points\":{},\"copies\":{},\"won_from\":[{}]}}",
+                report.number,
+                numbers.join(","),
+                report.points,
+                report.copies,
+                won_from.join(",")
+            )
+        })
+        .collect();
+
+    format!("[\n{}\n]\n", cards.join(",\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::StandardRules;
+
+    fn test_reports() -> Vec<CardReport> {
+        let lines = [
+            "Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53",
+            "Card 2: 13 32 20 16 61 | 61 30 68 82 17 32 24 19",
+            "Card 3:  1 21 53 59 44 | 69 82 63 72 16 21 14  1",
+        ];
+        let table = CardTable::from_lines(lines.iter().map(|l| l.to_string()));
+        card_reports(&table, &StandardRules::default(), Resolution::Ascending).unwrap()
+    }
+
+    #[test]
+    fn card_reports_work() {
+        let reports = test_reports();
+
+        assert_eq!(
+            reports[2],
+            CardReport {
+                number: 3,
+                matching_numbers: vec![1, 21],
+                points: 2,
+                copies: 4,
+                won_from: vec![(1, 1), (2, 2)],
+            }
+        );
+    }
+
+    #[test]
+    fn render_works() {
+        let reports = test_reports();
+
+        assert_eq!(
+            render(&reports, ReportFormat::Table),
+            "\
+card  matches  numbers      points  copies  won from
+   1        4  17 48 83 86       8       1
+   2        2  32 61             2       2  1x1
+   3        2  1 21              2       4  1x1 2x2
+"
+        );
+        assert_eq!(
+            render(&reports, ReportFormat::Csv),
+            "\
+card,matches,numbers,points,copies,won_from
+1,4,17 48 83 86,8,1,
+2,2,32 61,2,2,1x1
+3,2,1 21,2,4,1x1 2x2
+"
+        );
+        assert_eq!(
+            render(&reports[1..2], ReportFormat::Json),
+            "[\n  {\"card\":2,\"matching_numbers\":[32,61],\"points\":2,\"copies\":2,\"won_from\":[{\"card\":1,\"copies\":1}]}\n]\n"
+        );
+    }
+}
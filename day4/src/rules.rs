@@ -0,0 +1,171 @@
+use crate::Card;
+
+/// Decides how many points a card is worth and which cards it wins copies of. Implement this to
+/// score new variants of the game.
+pub trait ScoringRule {
+    /// returns the points the card is worth, or None if they don't fit in a u64
+    fn points(&self, card: &Card) -> Option<u64>;
+
+    /// returns the card numbers the card wins a copy of. Numbers of cards that aren't in the deck
+    /// are ignored.
+    fn wins(&self, card: &Card) -> Vec<u32>;
+}
+
+/// The built-in scoring rules, made of a points rule and a copy rule
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct StandardRules {
+    pub points: Points,
+    pub copies: Copies,
+}
+
+impl ScoringRule for StandardRules {
+    fn points(&self, card: &Card) -> Option<u64> {
+        self.points.score(card.num_winning())
+    }
+
+    fn wins(&self, card: &Card) -> Vec<u32> {
+        self.copies.won_by(card)
+    }
+}
+
+/// How the number of matches on a card is turned into points
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum Points {
+    /// 1 point for the first match, doubled for every match after it
+    #[default]
+    Doubling,
+
+    /// 1 point per match
+    Linear,
+
+    /// the nth Fibonacci number for n matches
+    Fibonacci,
+
+    /// the points for n matches are the nth entry, matches past the end of the table score nothing
+    Lookup(Vec<u64>),
+}
+
+impl Points {
+    /// returns the points for the number of matches, or None if they don't fit in a u64. Doubling
+    /// overflows past 64 matches and Fibonacci past 93.
+    pub fn score(&self, matches: u32) -> Option<u64> {
+        match self {
+            Points::Doubling if matches == 0 => Some(0),
+            Points::Doubling => 1_u64.checked_shl(matches - 1),
+            Points::Linear => Some(u64::from(matches)),
+            Points::Fibonacci => {
+                let (mut a, mut b) = (0_u64, 1_u64);
+                for _ in 0..matches {
+                    (a, b) = (b, a.saturating_add(b));
+                }
+                // b saturates one step before a does, so a is exact until it saturates too
+                (a != u64::MAX).then_some(a)
+            }
+            Points::Lookup(table) => Some(table.get(matches as usize).copied().unwrap_or(0)),
+        }
+    }
+}
+
+/// Which cards a card wins copies of
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Copies {
+    /// one copy of each of the n cards after it, for n matches
+    #[default]
+    NextN,
+
+    /// one copy of each of the n cards before it, for n matches
+    PreviousN,
+
+    /// one copy of every kth card after it, n times for n matches
+    EveryKth(u32),
+
+    /// one copy of each card whose number is one of the matching winning numbers
+    ByWinningValue,
+}
+
+impl Copies {
+    pub fn won_by(&self, card: &Card) -> Vec<u32> {
+        let number = card.number();
+        let matches = card.num_winning();
+
+        // card numbers past u32::MAX can't be in the deck, so they are left out
+        match self {
+            Copies::NextN => (1..=matches).map_while(|i| number.checked_add(i)).collect(),
+            Copies::PreviousN => (1..=matches.min(number.saturating_sub(1)))
+                .map(|i| number - i)
+                .collect(),
+            Copies::EveryKth(k) => (1..=matches)
+                .map_while(|i| {
+                    i.checked_mul(*k)
+                        .and_then(|offset| number.checked_add(offset))
+                })
+                .collect(),
+            Copies::ByWinningValue => card.matching_numbers().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn points_work() {
+        let scores = |points: Points| (0..6).map(|n| points.score(n).unwrap()).collect::<Vec<_>>();
+
+        assert_eq!(scores(Points::Doubling), vec![0, 1, 2, 4, 8, 16]);
+        assert_eq!(scores(Points::Linear), vec![0, 1, 2, 3, 4, 5]);
+        assert_eq!(scores(Points::Fibonacci), vec![0, 1, 1, 2, 3, 5]);
+        assert_eq!(
+            scores(Points::Lookup(vec![0, 10, 20])),
+            vec![0, 10, 20, 0, 0, 0]
+        );
+    }
+
+    #[test]
+    fn large_scores_work() {
+        assert_eq!(Points::Doubling.score(33), Some(1 << 32));
+        assert_eq!(Points::Doubling.score(64), Some(1 << 63));
+        assert_eq!(Points::Doubling.score(65), None);
+        assert_eq!(
+            Points::Fibonacci.score(93),
+            Some(12_200_160_415_121_876_738)
+        );
+        assert_eq!(Points::Fibonacci.score(94), None);
+
+        // 40 matching numbers
+        let numbers: Vec<String> = (1..=40).map(|n| n.to_string()).collect();
+        let numbers = numbers.join(" ");
+        let card = Card::from_line(&format!("Card 1: {} | {}", numbers, numbers)).unwrap();
+        let rules = StandardRules::default();
+        assert_eq!(card.num_winning(), 40);
+        assert_eq!(rules.points(&card), Some(1 << 39));
+
+        // copies stop at the largest card number
+        let card = Card::from_line(&format!("Card 4294967290: {} | {}", numbers, numbers)).unwrap();
+        assert_eq!(Copies::NextN.won_by(&card).len(), 5);
+        assert_eq!(
+            Copies::EveryKth(2).won_by(&card),
+            vec![4294967292, 4294967294]
+        );
+        assert_eq!(rules.wins(&card).last(), Some(&u32::MAX));
+    }
+
+    #[test]
+    fn copies_work() {
+        // card 3 has 2 matches, 48 and 17
+        let card = Card::from_line("Card 3: 41 48 83 86 17 | 48 17 99").unwrap();
+
+        assert_eq!(Copies::NextN.won_by(&card), vec![4, 5]);
+        assert_eq!(Copies::PreviousN.won_by(&card), vec![2, 1]);
+        assert_eq!(Copies::EveryKth(3).won_by(&card), vec![6, 9]);
+
+        let mut by_value = Copies::ByWinningValue.won_by(&card);
+        by_value.sort();
+        assert_eq!(by_value, vec![17, 48]);
+
+        // card 1 has no cards before it
+        let card = Card::from_line("Card 1: 41 48 83 86 17 | 48 17 99").unwrap();
+        assert!(Copies::PreviousN.won_by(&card).is_empty());
+    }
+}
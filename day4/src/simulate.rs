@@ -0,0 +1,307 @@
+use std::collections::BTreeMap;
+
+use num_bigint::BigUint;
+use num_traits::ToPrimitive;
+
+use crate::{Card, CardError, CardTable, Points, Resolution, ScoringRule};
+
+/// Describes how random decks are dealt. Each card's winning numbers and owned numbers are each
+/// drawn without replacement from 1 to `pool`, independently of each other.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DeckConfig {
+    /// the number of cards in a deck
+    pub cards: u32,
+
+    /// the size of the pool of numbers drawn from
+    pub pool: u32,
+
+    /// how many winning numbers are drawn for each card
+    pub winning: u32,
+
+    /// how many owned numbers are drawn for each card
+    pub owned: u32,
+}
+
+/// The shape of the puzzle input: 200 cards with 10 winning and 25 owned numbers from 1 to 99
+impl Default for DeckConfig {
+    fn default() -> Self {
+        Self {
+            cards: 200,
+            pool: 99,
+            winning: 10,
+            owned: 25,
+        }
+    }
+}
+
+impl DeckConfig {
+    fn validate(&self) -> Result<(), CardError> {
+        if self.winning > self.pool || self.owned > self.pool {
+            return Err(CardError::InvalidDeck(format!(
+                "can't draw {} winning and {} owned numbers from a pool of {}",
+                self.winning, self.owned, self.pool
+            )));
+        }
+        Ok(())
+    }
+
+    /// deals a deck from the seed. The same seed always deals the same deck.
+    pub fn deal(&self, seed: u64) -> Result<Vec<Card>, CardError> {
+        self.validate()?;
+
+        let mut rng = SplitMix64(seed);
+        let mut pool: Vec<u32> = (1..=self.pool).collect();
+
+        let deck = (1..=self.cards)
+            .map(|number| {
+                let winning_numbers = rng.draw(&mut pool, self.winning).to_vec();
+                let numbers = rng.draw(&mut pool, self.owned);
+                Card::new(number, &winning_numbers, numbers)
+            })
+            .collect();
+
+        Ok(deck)
+    }
+
+    /// the exact probability that a card has each number of matches, from 0 up to the most
+    /// possible. The number of matches follows a hypergeometric distribution: the owned numbers
+    /// are a draw from the pool, and the winning numbers are the successes in it.
+    pub fn match_probabilities(&self) -> Result<Vec<f64>, CardError> {
+        self.validate()?;
+
+        let (pool, winning, owned) = (self.pool, self.winning, self.owned);
+        let ln_total = ln_binomial(pool, owned);
+
+        let probabilities = (0..=winning.min(owned))
+            .map(|k| {
+                if owned - k > pool - winning {
+                    return 0.0;
+                }
+                (ln_binomial(winning, k) + ln_binomial(pool - winning, owned - k) - ln_total).exp()
+            })
+            .collect();
+
+        Ok(probabilities)
+    }
+
+    /// the exact expected points of a single card. Fails with `PointsOverflow` if a card can
+    /// have more matches than the points can score, even if that is unlikely.
+    pub fn expected_points(&self, points: &Points) -> Result<f64, CardError> {
+        let probabilities = self.match_probabilities()?;
+
+        probabilities
+            .iter()
+            .enumerate()
+            .map(|(k, p)| {
+                let score = points
+                    .score(k as u32)
+                    .ok_or(CardError::PointsOverflow { card: None })?;
+                Ok(p * score as f64)
+            })
+            .sum()
+    }
+}
+
+/// The totals of many simulated decks
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulationReport {
+    pub runs: usize,
+
+    /// the mean and sample variance of the total points of a deck
+    pub points: Summary,
+
+    /// the mean and sample variance of the total cards held at the end of a deck. Copies grow
+    /// exponentially through random decks, so the totals are counted exactly and summarised as
+    /// floats.
+    pub cards: Summary,
+
+    /// how many decks ended with each total number of cards
+    pub card_distribution: BTreeMap<BigUint, usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Summary {
+    pub mean: f64,
+    pub variance: f64,
+}
+
+impl Summary {
+    fn of(values: &[f64]) -> Self {
+        let n = values.len() as f64;
+        if values.is_empty() {
+            return Self {
+                mean: 0.0,
+                variance: 0.0,
+            };
+        }
+
+        let mean = values.iter().sum::<f64>() / n;
+        let variance = if values.len() < 2 {
+            0.0
+        } else {
+            values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1.0)
+        };
+
+        Self { mean, variance }
+    }
+}
+
+/// deals a deck for each seed, and scores each one by the rule. Fails with `PointsOverflow` if a
+/// dealt card or a deck scores more points than fit in a u64.
+pub fn simulate<R: ScoringRule + ?Sized>(
+    config: &DeckConfig,
+    rule: &R,
+    resolution: Resolution,
+    seeds: impl IntoIterator<Item = u64>,
+) -> Result<SimulationReport, CardError> {
+    let mut points = Vec::new();
+    let mut cards = Vec::new();
+    let mut card_distribution = BTreeMap::new();
+
+    for seed in seeds {
+        let table = CardTable::from_cards(config.deal(seed)?);
+
+        let deck_points = table.cards().try_fold(0_u64, |total, card| {
+            let points = rule.points(card).ok_or(CardError::PointsOverflow {
+                card: Some(card.number()),
+            })?;
+            total
+                .checked_add(points)
+                .ok_or(CardError::PointsOverflow { card: None })
+        })?;
+        points.push(deck_points as f64);
+
+        let copies: BTreeMap<u32, BigUint> = table.copies(rule, resolution)?;
+        let total: BigUint = copies.values().sum();
+        cards.push(total.to_f64().unwrap_or(f64::INFINITY));
+        *card_distribution.entry(total).or_insert(0) += 1;
+    }
+
+    Ok(SimulationReport {
+        runs: points.len(),
+        points: Summary::of(&points),
+        cards: Summary::of(&cards),
+        card_distribution,
+    })
+}
+
+/// the natural log of n choose k
+fn ln_binomial(n: u32, k: u32) -> f64 {
+    let k = k.min(n - k);
+    (1..=k)
+        .map(|i| (f64::from(n - k + i) / f64::from(i)).ln())
+        .sum()
+}
+
+/// A small, fast generator, so that simulations are reproducible from their seed
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// shuffles `count` random numbers to the front of the pool and returns them
+    fn draw<'a>(&mut self, pool: &'a mut [u32], count: u32) -> &'a [u32] {
+        let count = count as usize;
+        for i in 0..count {
+            let j = i + (self.next() % (pool.len() - i) as u64) as usize;
+            pool.swap(i, j);
+        }
+        &pool[..count]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::StandardRules;
+
+    #[test]
+    fn match_probabilities_work() {
+        // 2 winning and 2 owned numbers from 1 to 4: of the 6 ways to pick the owned numbers, 1
+        // misses both winning numbers, 4 hit one and 1 hits both
+        let config = DeckConfig {
+            cards: 1,
+            pool: 4,
+            winning: 2,
+            owned: 2,
+        };
+        let probabilities = config.match_probabilities().unwrap();
+        for (p, expected) in probabilities.iter().zip([1.0 / 6.0, 4.0 / 6.0, 1.0 / 6.0]) {
+            assert!((p - expected).abs() < 1e-12);
+        }
+        assert!((config.expected_points(&Points::Doubling).unwrap() - 1.0).abs() < 1e-12);
+
+        let total: f64 = DeckConfig::default()
+            .match_probabilities()
+            .unwrap()
+            .iter()
+            .sum();
+        assert!((total - 1.0).abs() < 1e-12);
+
+        assert!(matches!(
+            DeckConfig { pool: 1, ..config }.match_probabilities(),
+            Err(CardError::InvalidDeck(_))
+        ));
+    }
+
+    #[test]
+    fn simulate_works() {
+        let config = DeckConfig::default();
+        let rule = StandardRules::default();
+        let report = simulate(&config, &rule, Resolution::Ascending, 0..200).unwrap();
+
+        assert_eq!(report.runs, 200);
+        assert_eq!(report.card_distribution.values().sum::<usize>(), 200);
+
+        // the simulated mean points per deck is close to the exact expectation
+        let expected = config.expected_points(&Points::Doubling).unwrap() * f64::from(config.cards);
+        let standard_error = (report.points.variance / report.runs as f64).sqrt();
+        assert!((report.points.mean - expected).abs() < 5.0 * standard_error);
+
+        // the same seeds always deal the same decks
+        assert_eq!(
+            report,
+            simulate(&config, &rule, Resolution::Ascending, 0..200).unwrap()
+        );
+    }
+
+    #[test]
+    fn large_decks_work() {
+        // up to 40 matches a card, which scores past a u32 but fits in a u64
+        let config = DeckConfig {
+            pool: 100,
+            winning: 40,
+            owned: 40,
+            ..DeckConfig::default()
+        };
+        let rule = StandardRules::default();
+        assert!(config.expected_points(&Points::Doubling).unwrap() > 0.0);
+        assert_eq!(
+            simulate(&config, &rule, Resolution::Ascending, 0..5)
+                .unwrap()
+                .runs,
+            5
+        );
+
+        // at least 80 matches a card, which no longer fits in a u64
+        let config = DeckConfig {
+            winning: 90,
+            owned: 90,
+            ..config
+        };
+        assert!(matches!(
+            config.expected_points(&Points::Doubling),
+            Err(CardError::PointsOverflow { card: None })
+        ));
+        assert!(matches!(
+            simulate(&config, &rule, Resolution::Ascending, 0..5),
+            Err(CardError::PointsOverflow { .. })
+        ));
+    }
+}
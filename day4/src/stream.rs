@@ -0,0 +1,267 @@
+use std::collections::VecDeque;
+use std::io::BufRead;
+
+use crate::{Card, CardError, Counter, TableIssue};
+
+/// The running count after a card has been read
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunningTotal<C> {
+    pub card: u32,
+
+    /// the copies held of this card, including the original
+    pub copies: C,
+
+    /// the copies held of this card and every card before it
+    pub total: C,
+}
+
+/// Counts copies card by card as a deck is read, with cards winning copies of the next n cards for
+/// n matches. Only the copies won for the cards still to come are kept, in a ring buffer that is
+/// never longer than the most matches on a card, so decks of any length are counted in constant
+/// memory. Cards must be numbered in order without gaps.
+pub struct CardStream<B, C = u64> {
+    lines: std::io::Lines<B>,
+
+    /// the line number of the last line read, starting from 1
+    line: usize,
+
+    /// the copies won so far for each of the cards after the last card read
+    pending: VecDeque<C>,
+
+    last_card_number: Option<u32>,
+
+    total: C,
+
+    /// set once an error has been returned, so the stream stops
+    failed: bool,
+}
+
+impl<B: BufRead, C: Counter> CardStream<B, C> {
+    pub fn new(reader: B) -> Self {
+        Self {
+            lines: reader.lines(),
+            line: 0,
+            pending: VecDeque::new(),
+            last_card_number: None,
+            total: C::zero(),
+            failed: false,
+        }
+    }
+
+    /// the number of cards ahead that copies are being held for
+    pub fn buffered(&self) -> usize {
+        self.pending.len()
+    }
+
+    fn next_card(&mut self) -> Option<Result<Card, CardError>> {
+        for line in self.lines.by_ref() {
+            self.line += 1;
+            match line {
+                Ok(line) => {
+                    if let Some(card) = Card::from_line(&line) {
+                        return Some(Ok(card));
+                    }
+                }
+                Err(e) => return Some(Err(e.into())),
+            }
+        }
+        None
+    }
+
+    /// checks the card follows straight on from the last card
+    fn check_sequence(&self, number: u32) -> Result<(), CardError> {
+        let Some(last) = self.last_card_number else {
+            return Ok(());
+        };
+
+        let issue = if number == last {
+            TableIssue::Duplicate {
+                number,
+                line: self.line,
+            }
+        } else if number < last {
+            TableIssue::OutOfOrder {
+                number,
+                line: self.line,
+            }
+        } else if number > last + 1 {
+            TableIssue::Missing {
+                numbers: last + 1..number,
+            }
+        } else {
+            return Ok(());
+        };
+
+        Err(CardError::InvalidTable(vec![issue]))
+    }
+
+    fn count(&mut self, card: Card) -> Result<RunningTotal<C>, CardError> {
+        let number = card.number();
+        self.check_sequence(number)?;
+        self.last_card_number = Some(number);
+
+        let overflow = || CardError::CountOverflow { card: Some(number) };
+
+        // the original plus every copy won by the cards before it
+        let won = self.pending.pop_front().unwrap_or_else(C::zero);
+        let copies = won.checked_add(&C::one()).ok_or_else(overflow)?;
+
+        let matches = card.num_winning() as usize;
+        if self.pending.len() < matches {
+            self.pending.resize(matches, C::zero());
+        }
+        for pending in self.pending.iter_mut().take(matches) {
+            *pending = pending.checked_add(&copies).ok_or_else(overflow)?;
+        }
+
+        self.total = self
+            .total
+            .checked_add(&copies)
+            .ok_or(CardError::CountOverflow { card: None })?;
+
+        Ok(RunningTotal {
+            card: number,
+            copies,
+            total: self.total.clone(),
+        })
+    }
+}
+
+impl<B: BufRead, C: Counter> Iterator for CardStream<B, C> {
+    type Item = Result<RunningTotal<C>, CardError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+
+        let result = self.next_card()?.and_then(|card| self.count(card));
+        self.failed = result.is_err();
+        Some(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufReader, Read};
+
+    use super::*;
+    use crate::{CardTable, Resolution, StandardRules};
+
+    /// generates a deck line by line without holding it in memory. Every card has 10 winning numbers
+    /// and 10 owned numbers, and the first `matches` owned numbers are winning numbers.
+    struct Deck {
+        cards: u32,
+        next_card: u32,
+        buffer: Vec<u8>,
+        state: u64,
+    }
+
+    impl Deck {
+        fn new(cards: u32) -> Self {
+            Self {
+                cards,
+                next_card: 1,
+                buffer: Vec::new(),
+                state: 0x9e37_79b9_7f4a_7c15,
+            }
+        }
+
+        fn random(&mut self) -> u64 {
+            self.state ^= self.state << 13;
+            self.state ^= self.state >> 7;
+            self.state ^= self.state << 17;
+            self.state
+        }
+    }
+
+    impl Read for Deck {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            if self.buffer.is_empty() && self.next_card <= self.cards {
+                let matches = (self.random() % 4) as usize;
+                let owned: Vec<u32> = (1..=10)
+                    .map(|i| {
+                        if i <= matches {
+                            i as u32
+                        } else {
+                            50 + i as u32
+                        }
+                    })
+                    .collect();
+                let owned: Vec<String> = owned.iter().map(|n| n.to_string()).collect();
+                self.buffer = format!(
+                    "Card {}: 1 2 3 4 5 6 7 8 9 10 | {}\n",
+                    self.next_card,
+                    owned.join(" ")
+                )
+                .into_bytes();
+                self.next_card += 1;
+            }
+
+            let n = buf.len().min(self.buffer.len());
+            buf[..n].copy_from_slice(&self.buffer[..n]);
+            self.buffer.drain(..n);
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn stream_works() {
+        let reader = BufReader::new(std::fs::File::open("test_input").unwrap());
+        let totals: Vec<RunningTotal<u64>> = CardStream::new(reader).map(Result::unwrap).collect();
+
+        assert_eq!(
+            totals
+                .iter()
+                .map(|t| (t.card, t.copies, t.total))
+                .collect::<Vec<_>>(),
+            vec![
+                (1, 1, 1),
+                (2, 2, 3),
+                (3, 4, 7),
+                (4, 8, 15),
+                (5, 14, 29),
+                (6, 1, 30)
+            ]
+        );
+
+        // a generated deck agrees with the card table
+        let mut lines = String::new();
+        Deck::new(2000).read_to_string(&mut lines).unwrap();
+        let table = CardTable::from_lines(lines.lines().map(String::from));
+        let copies: std::collections::BTreeMap<u32, u64> = table
+            .copies(&StandardRules::default(), Resolution::Ascending)
+            .unwrap();
+        let last = CardStream::<_, u64>::new(BufReader::new(Deck::new(2000)))
+            .last()
+            .unwrap()
+            .unwrap();
+        assert_eq!(last.total, copies.values().sum::<u64>());
+    }
+
+    #[test]
+    fn stream_memory_is_bounded() {
+        let mut stream = CardStream::<_, u128>::new(BufReader::new(Deck::new(200_000)));
+        let mut cards = 0;
+
+        while let Some(total) = stream.next() {
+            total.unwrap();
+            assert!(stream.buffered() <= 3);
+            cards += 1;
+        }
+
+        assert_eq!(cards, 200_000);
+    }
+
+    #[test]
+    fn stream_rejects_gaps() {
+        let lines = "Card 1: 1 | 1\nCard 3: 1 | 1\n";
+        let results: Vec<_> = CardStream::<_, u64>::new(lines.as_bytes()).collect();
+
+        assert_eq!(results.len(), 2);
+        assert!(matches!(
+            &results[1],
+            Err(CardError::InvalidTable(issues)) if issues == &[TableIssue::Missing { numbers: 2..3 }]
+        ));
+    }
+}
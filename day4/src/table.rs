@@ -0,0 +1,313 @@
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::fmt;
+use std::ops::Range;
+
+use crate::{Card, CardError, Counter, ParseError, ScoringRule, Side, StrictParser};
+
+/// All the cards of a deck, indexed by card number
+#[derive(Debug, Default)]
+pub struct CardTable {
+    cards: BTreeMap<u32, Card>,
+
+    /// problems found while building the table
+    issues: Vec<TableIssue>,
+}
+
+/// A problem with the card lines a table was built from
+#[derive(Debug, Clone, PartialEq)]
+pub enum TableIssue {
+    /// a card number appeared more than once, only the first card is kept
+    Duplicate { number: u32, line: usize },
+
+    /// a card number is lower than the card number on the line before it
+    OutOfOrder { number: u32, line: usize },
+
+    /// a run of card numbers between the lowest and highest card numbers never appeared
+    Missing { numbers: Range<u32> },
+
+    /// a number appeared more than once on the same side of a card
+    DuplicateNumber {
+        number: u32,
+        side: Side,
+        line: usize,
+        column: usize,
+    },
+}
+
+impl TableIssue {
+    /// issues that make it impossible to count copies
+    fn is_fatal(&self) -> bool {
+        !matches!(
+            self,
+            TableIssue::OutOfOrder { .. } | TableIssue::DuplicateNumber { .. }
+        )
+    }
+}
+
+impl fmt::Display for TableIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TableIssue::Duplicate { number, line } => {
+                write!(f, "line {}: card {} appears more than once", line, number)
+            }
+            TableIssue::OutOfOrder { number, line } => {
+                write!(f, "line {}: card {} is out of order", line, number)
+            }
+            TableIssue::Missing { numbers } if numbers.len() == 1 => {
+                write!(f, "card {} is missing", numbers.start)
+            }
+            TableIssue::Missing { numbers } => write!(
+                f,
+                "cards {} to {} are missing",
+                numbers.start,
+                numbers.end - 1
+            ),
+            TableIssue::DuplicateNumber {
+                number,
+                side,
+                line,
+                column,
+            } => {
+                let side = match side {
+                    Side::Winning => "winning",
+                    Side::Owned => "owned",
+                };
+                write!(
+                    f,
+                    "line {}, column {}: {} number {} appears more than once",
+                    line, column, side, number
+                )
+            }
+        }
+    }
+}
+
+/// The order in which cards hand their copies on to the cards they win
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Resolution {
+    /// resolve cards from the lowest card number up, which requires every card to only win
+    /// copies of cards after it
+    Ascending,
+
+    /// resolve a card once every card that wins copies of it has been resolved, which allows
+    /// cards to win copies of earlier cards as long as no card wins copies of itself
+    Topological,
+}
+
+impl CardTable {
+    /// builds a table from card lines, skipping lines that aren't cards. Line numbers in issues
+    /// start from 1.
+    pub fn from_lines(lines: impl Iterator<Item = String>) -> Self {
+        let mut table = Self::default();
+        let mut last_card_number = None;
+
+        for (idx, line) in lines.enumerate() {
+            if let Some(card) = Card::from_line(&line) {
+                table.insert(card, idx + 1, &mut last_card_number);
+            }
+        }
+
+        table.find_missing();
+        table
+    }
+
+    /// builds a table from cards that have already been parsed or generated. Line numbers in
+    /// issues are positions in the iterator, starting from 1.
+    pub fn from_cards(cards: impl IntoIterator<Item = Card>) -> Self {
+        let mut table = Self::default();
+        let mut last_card_number = None;
+
+        for (idx, card) in cards.into_iter().enumerate() {
+            table.insert(card, idx + 1, &mut last_card_number);
+        }
+
+        table.find_missing();
+        table
+    }
+
+    /// builds a table from card lines with the strict parser, skipping blank lines. Every line
+    /// that can't be parsed is reported, and duplicate numbers on a card are recorded as issues.
+    pub fn from_lines_strict(lines: impl Iterator<Item = String>) -> Result<Self, CardError> {
+        let mut table = Self::default();
+        let mut last_card_number = None;
+        let mut parser = StrictParser::new();
+        let mut errors: Vec<ParseError> = Vec::new();
+
+        for (idx, line) in lines.enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            match parser.parse_line(&line, idx + 1) {
+                Ok((card, duplicates)) => {
+                    for duplicate in duplicates {
+                        table.issues.push(TableIssue::DuplicateNumber {
+                            number: duplicate.number,
+                            side: duplicate.side,
+                            line: idx + 1,
+                            column: duplicate.column,
+                        });
+                    }
+                    table.insert(card, idx + 1, &mut last_card_number);
+                }
+                Err(error) => errors.push(error),
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(CardError::Parse(errors));
+        }
+
+        table.find_missing();
+        Ok(table)
+    }
+
+    /// adds a card read from the line, recording it as out of order or duplicated if needed
+    fn insert(&mut self, card: Card, line: usize, last_card_number: &mut Option<u32>) {
+        let number = card.number;
+
+        if last_card_number.is_some_and(|last| number < last) {
+            self.issues.push(TableIssue::OutOfOrder { number, line });
+        }
+        *last_card_number = Some(number);
+
+        match self.cards.entry(number) {
+            Entry::Occupied(_) => self.issues.push(TableIssue::Duplicate { number, line }),
+            Entry::Vacant(entry) => {
+                entry.insert(card);
+            }
+        }
+    }
+
+    /// looks for gaps between the lowest and highest card numbers, reporting each gap once
+    fn find_missing(&mut self) {
+        let numbers: Vec<u32> = self.cards.keys().copied().collect();
+        for pair in numbers.windows(2) {
+            if pair[0] + 1 < pair[1] {
+                self.issues.push(TableIssue::Missing {
+                    numbers: pair[0] + 1..pair[1],
+                });
+            }
+        }
+    }
+
+    pub fn issues(&self) -> &[TableIssue] {
+        &self.issues
+    }
+
+    pub fn get(&self, number: u32) -> Option<&Card> {
+        self.cards.get(&number)
+    }
+
+    /// iterates over the cards in ascending card number order
+    pub fn cards(&self) -> impl Iterator<Item = &Card> {
+        self.cards.values()
+    }
+
+    /// returns the card numbers that the card wins copies of under the rule. Cards can't win
+    /// copies of cards that aren't in the table.
+    pub(crate) fn wins<R: ScoringRule + ?Sized>(&self, rule: &R, card: &Card) -> Vec<u32> {
+        rule.wins(card)
+            .into_iter()
+            .filter(|n| self.cards.contains_key(n))
+            .collect()
+    }
+
+    /// counts how many copies of each card are held once every card has been resolved
+    pub fn copies<C: Counter, R: ScoringRule + ?Sized>(
+        &self,
+        rule: &R,
+        resolution: Resolution,
+    ) -> Result<BTreeMap<u32, C>, CardError> {
+        let fatal: Vec<TableIssue> = self
+            .issues
+            .iter()
+            .filter(|i| i.is_fatal())
+            .cloned()
+            .collect();
+        if !fatal.is_empty() {
+            return Err(CardError::InvalidTable(fatal));
+        }
+
+        let order = match resolution {
+            Resolution::Ascending => self.ascending_order(rule)?,
+            Resolution::Topological => self.topological_order(rule)?,
+        };
+
+        // every card starts with the original
+        let mut copies: BTreeMap<u32, C> = self.cards.keys().map(|&n| (n, C::one())).collect();
+
+        for number in order {
+            let num_copies = copies[&number].clone();
+            for won in self.wins(rule, &self.cards[&number]) {
+                let count = copies.get_mut(&won).expect("won card is in the table");
+                *count = count
+                    .checked_add(&num_copies)
+                    .ok_or(CardError::CountOverflow { card: Some(won) })?;
+            }
+        }
+
+        Ok(copies)
+    }
+
+    fn ascending_order<R: ScoringRule + ?Sized>(&self, rule: &R) -> Result<Vec<u32>, CardError> {
+        for card in self.cards.values() {
+            if let Some(&to) = self.wins(rule, card).iter().find(|&&n| n <= card.number) {
+                return Err(CardError::BackwardWin {
+                    from: card.number,
+                    to,
+                });
+            }
+        }
+
+        Ok(self.cards.keys().copied().collect())
+    }
+
+    /// orders cards so that every card comes after all the cards that win copies of it
+    fn topological_order<R: ScoringRule + ?Sized>(&self, rule: &R) -> Result<Vec<u32>, CardError> {
+        let mut winners_left: HashMap<u32, usize> = self.cards.keys().map(|&n| (n, 0)).collect();
+
+        for card in self.cards.values() {
+            for won in self.wins(rule, card) {
+                *winners_left
+                    .get_mut(&won)
+                    .expect("won card is in the table") += 1;
+            }
+        }
+
+        // start from the cards nobody wins copies of, in card number order
+        let mut ready: VecDeque<u32> = self
+            .cards
+            .keys()
+            .copied()
+            .filter(|n| winners_left[n] == 0)
+            .collect();
+        let mut order = Vec::with_capacity(self.cards.len());
+
+        while let Some(number) = ready.pop_front() {
+            order.push(number);
+
+            for won in self.wins(rule, &self.cards[&number]) {
+                let left = winners_left
+                    .get_mut(&won)
+                    .expect("won card is in the table");
+                *left -= 1;
+                if *left == 0 {
+                    ready.push_back(won);
+                }
+            }
+        }
+
+        if order.len() < self.cards.len() {
+            let stuck = winners_left
+                .into_iter()
+                .filter(|(_, left)| *left > 0)
+                .map(|(n, _)| n)
+                .collect::<BTreeSet<_>>();
+            return Err(CardError::Cycle(stuck.into_iter().collect()));
+        }
+
+        Ok(order)
+    }
+}
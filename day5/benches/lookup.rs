@@ -0,0 +1,74 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use day5::{Map, MapBuilder, SubMap};
+
+const RANGES: i64 = 100_000;
+const STAGES: usize = 7;
+
+/// generates a stage that splits 0..RANGES * 1000 into RANGES ranges of random lengths, and sends
+/// them to a shuffled order of the same destinations, like a very large puzzle input
+fn stage(state: &mut u64) -> Map {
+    let mut next = move || {
+        *state ^= *state << 13;
+        *state ^= *state >> 7;
+        *state ^= *state << 17;
+        *state
+    };
+
+    let lengths: Vec<i64> = (0..RANGES).map(|_| (next() % 1999 + 1) as i64).collect();
+    let mut order: Vec<usize> = (0..lengths.len()).collect();
+    for i in (1..order.len()).rev() {
+        order.swap(i, (next() % (i as u64 + 1)) as usize);
+    }
+
+    let mut destination_starts = vec![0; lengths.len()];
+    let mut destination = 0;
+    for &i in &order {
+        destination_starts[i] = destination;
+        destination += lengths[i];
+    }
+
+    let mut builder = MapBuilder::new();
+    let mut source = 0;
+    for (length, destination_start) in lengths.iter().zip(destination_starts) {
+        builder.add(SubMap::new(source, destination_start, *length));
+        source += length;
+    }
+    builder.build().unwrap()
+}
+
+fn lookup(c: &mut Criterion) {
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let stages: Vec<Map> = (0..STAGES).map(|_| stage(&mut state)).collect();
+    let composed = stages
+        .iter()
+        .fold(Map::identity(), |composed, map| composed.compose(map));
+    let keys: Vec<i64> = (0..RANGES).map(|i| i * 997).collect();
+
+    let mut group = c.benchmark_group("lookup");
+    group.sample_size(10);
+
+    group.bench_function("compose", |b| {
+        b.iter(|| {
+            stages.iter().fold(Map::identity(), |composed, map| {
+                composed.compose(black_box(map))
+            })
+        })
+    });
+
+    group.bench_function("apply", |b| {
+        b.iter(|| keys.iter().map(|&key| composed.apply(black_box(key))).min())
+    });
+
+    group.bench_function("map_range", |b| {
+        b.iter(|| {
+            keys.windows(2)
+                .map(|pair| stages[0].map_range(black_box(pair[0]), pair[1]).len())
+                .sum::<usize>()
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, lookup);
+criterion_main!(benches);
@@ -0,0 +1,473 @@
+use std::fmt;
+use std::io::BufRead;
+use std::ops::Range;
+
+use crate::graph::parse_header;
+use crate::{AlmanacError, CategoryGraph, Map, MapBuilder, MapIssue, Overlaps};
+
+/// A line of a map section, such as "50 98 2"
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MapLine {
+    pub destination_start: i64,
+    pub source_start: i64,
+    pub length: i64,
+}
+
+/// A map section as it was written: the categories in its header and its lines in order
+#[derive(Debug, Clone, PartialEq)]
+pub struct MapSection {
+    pub source: String,
+    pub destination: String,
+    pub lines: Vec<MapLine>,
+}
+
+/// A parsed almanac: the seeds line, the map sections, and the graph of the categories they
+/// convert between
+#[derive(Debug)]
+pub struct Almanac {
+    seeds: Vec<i64>,
+    sections: Vec<MapSection>,
+    graph: CategoryGraph,
+
+    /// the issues that didn't stop a map being built, with the header of its section
+    warnings: Vec<(String, MapIssue)>,
+}
+
+impl Almanac {
+    /// parses an almanac, rejecting maps with overlapping ranges
+    pub fn parse<R: BufRead>(reader: R) -> Result<Self, AlmanacError> {
+        Self::parse_with(reader, Overlaps::Reject)
+    }
+
+    /// parses an almanac, resolving ranges that overlap within a map by the policy
+    pub fn parse_with<R: BufRead>(reader: R, overlaps: Overlaps) -> Result<Self, AlmanacError> {
+        // pair each line with its line number, starting from 1
+        let mut lines = reader.lines().zip(1..);
+
+        let (seeds_line, _) = lines.next().unwrap_or((Ok(String::new()), 1));
+        let seeds_line = seeds_line?;
+        let seeds = seeds_line
+            .strip_prefix("seeds:")
+            .and_then(parse_numbers)
+            .ok_or_else(|| invalid_line(1, &seeds_line))?;
+
+        let mut sections: Vec<(String, MapSection, MapBuilder)> = Vec::new();
+
+        for (line, line_number) in lines {
+            let line = line?;
+            if line.is_empty() {
+                continue;
+            }
+
+            if line.starts_with(|c: char| c.is_ascii_digit()) {
+                let (_, section, builder) = sections
+                    .last_mut()
+                    .ok_or_else(|| invalid_line(line_number, &line))?;
+                let [destination_start, source_start, length] = parse_numbers(&line)
+                    .and_then(|numbers| numbers.try_into().ok())
+                    .ok_or_else(|| invalid_line(line_number, &line))?;
+
+                builder.add_line(line_number, destination_start, source_start, length);
+                section.lines.push(MapLine {
+                    destination_start,
+                    source_start,
+                    length,
+                });
+            } else {
+                let (source, destination) = parse_header(&line)
+                    .ok_or_else(|| AlmanacError::MalformedHeader(line.clone()))?;
+                let section = MapSection {
+                    source: source.to_string(),
+                    destination: destination.to_string(),
+                    lines: Vec::new(),
+                };
+                sections.push((line, section, MapBuilder::new().overlaps(overlaps)));
+            }
+        }
+
+        let mut graph = CategoryGraph::new();
+        let mut parsed_sections = Vec::new();
+        let mut warnings = Vec::new();
+
+        for (header, section, builder) in sections {
+            let (map, issues) =
+                builder
+                    .build_with_warnings()
+                    .map_err(|issues| AlmanacError::InvalidMap {
+                        header: header.clone(),
+                        issues,
+                    })?;
+            warnings.extend(issues.into_iter().map(|issue| (header.clone(), issue)));
+            graph.add_map(&header, map)?;
+            parsed_sections.push(section);
+        }
+
+        Ok(Self {
+            seeds,
+            sections: parsed_sections,
+            graph,
+            warnings,
+        })
+    }
+
+    /// the numbers on the seeds line, each taken as a single seed
+    pub fn seeds(&self) -> &[i64] {
+        &self.seeds
+    }
+
+    /// the numbers on the seeds line, taken in pairs of range start and length. Fails if the
+    /// last seed has no length, or a range ends past the largest number.
+    pub fn seed_ranges(&self) -> Result<Vec<Range<i64>>, AlmanacError> {
+        self.seeds
+            .chunks(2)
+            .map(|pair| match *pair {
+                [start, length] => start.checked_add(length).map(|end| start..end).ok_or(
+                    AlmanacError::InvalidSeedRange {
+                        start,
+                        length: Some(length),
+                    },
+                ),
+                _ => Err(AlmanacError::InvalidSeedRange {
+                    start: pair[0],
+                    length: None,
+                }),
+            })
+            .collect()
+    }
+
+    /// the issues that didn't stop a map being built, such as zero length lines or empty
+    /// sections, each with the header of the section it is in
+    pub fn warnings(&self) -> &[(String, MapIssue)] {
+        &self.warnings
+    }
+
+    /// the map sections in the order they were written
+    pub fn sections(&self) -> &[MapSection] {
+        &self.sections
+    }
+
+    pub fn map(&self, source: &str, destination: &str) -> Option<&Map> {
+        self.graph.map(source, destination)
+    }
+
+    pub fn graph(&self) -> &CategoryGraph {
+        &self.graph
+    }
+
+    pub fn into_graph(self) -> CategoryGraph {
+        self.graph
+    }
+
+    /// the chain of categories from the first map's source to the last map's destination, such
+    /// as seed, soil, ..., location. Empty if there are no maps, and fails with `NoPath` if the
+    /// maps don't lead from one to the other.
+    pub fn chain(&self) -> Result<Vec<String>, AlmanacError> {
+        match (self.sections.first(), self.sections.last()) {
+            (Some(first), Some(last)) => self.graph.path(&first.source, &last.destination),
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    /// merges the maps along the path from one category to the other into a single map
+    pub fn compose(&self, from: &str, to: &str) -> Result<Map, AlmanacError> {
+        self.graph.compose(from, to)
+    }
+
+    /// the almanac as JSON, with the maps as they were written. The chain is null if the maps
+    /// don't form one.
+    pub fn to_json(&self) -> String {
+        let numbers = |numbers: &[i64]| {
+            let numbers: Vec<String> = numbers.iter().map(|n| n.to_string()).collect();
+            format!("[{}]", numbers.join(","))
+        };
+        let strings = |strings: &[String]| {
+            let strings: Vec<String> = strings.iter().map(|s| json_string(s)).collect();
+            format!("[{}]", strings.join(","))
+        };
+
+        let maps: Vec<String> = self
+            .sections
+            .iter()
+            .map(|section| {
+                let lines: Vec<String> = section
+                    .lines
+                    .iter()
+                    .map(|line| {
+                        format!(
+                            "{{\"destination_start\":{},\"source_start\":{},\"length\":{}}}",
+                            line.destination_start, line.source_start, line.length
+                        )
+                    })
+                    .collect();
+
+                format!(
+                    "    {{\"source\":{},\"destination\":{},\"lines\":[{}]}}",
+                    json_string(&section.source),
+                    json_string(&section.destination),
+                    lines.join(",")
+                )
+            })
+            .collect();
+
+        format!(
+            "{{\n  \"seeds\":{},\n  \"chain\":{},\n  \"maps\":[\n{}\n  ]\n}}\n",
+            numbers(&self.seeds),
+            self.chain()
+                .map_or("null".to_string(), |chain| strings(&chain)),
+            maps.join(",\n")
+        )
+    }
+}
+
+/// prints the almanac in the format it was parsed from
+impl fmt::Display for Almanac {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "seeds:")?;
+        for seed in &self.seeds {
+            write!(f, " {}", seed)?;
+        }
+
+        for section in &self.sections {
+            write!(f, "\n\n{}-to-{} map:", section.source, section.destination)?;
+            for line in &section.lines {
+                write!(
+                    f,
+                    "\n{} {} {}",
+                    line.destination_start, line.source_start, line.length
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn invalid_line(line: usize, text: &str) -> AlmanacError {
+    AlmanacError::InvalidLine {
+        line,
+        text: text.to_string(),
+    }
+}
+
+/// parses numbers separated by whitespace
+fn parse_numbers(text: &str) -> Option<Vec<i64>> {
+    text.split_whitespace().map(|s| s.parse().ok()).collect()
+}
+
+/// quotes and escapes a string for JSON
+fn json_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn parse_works() {
+        let almanac = Almanac::parse(fs::read_to_string("test_input").unwrap().as_bytes()).unwrap();
+
+        assert_eq!(almanac.seeds(), &[79, 14, 55, 13]);
+        assert_eq!(almanac.seed_ranges().unwrap(), vec![79..93, 55..68]);
+        assert_eq!(
+            almanac.chain().unwrap(),
+            [
+                "seed",
+                "soil",
+                "fertilizer",
+                "water",
+                "light",
+                "temperature",
+                "humidity",
+                "location"
+            ]
+        );
+        assert_eq!(almanac.sections().len(), 7);
+        assert_eq!(
+            almanac.sections()[0].lines[0],
+            MapLine {
+                destination_start: 50,
+                source_start: 98,
+                length: 2
+            }
+        );
+        assert_eq!(almanac.map("seed", "soil").unwrap().apply(79), 81);
+        assert_eq!(almanac.compose("seed", "location").unwrap().apply(79), 82);
+    }
+
+    #[test]
+    fn round_trip_works() {
+        for path in [
+            "test_input",
+            "input",
+            "tonys_input",
+            "tims_input",
+            "diggseys_input",
+        ] {
+            let text = fs::read_to_string(path).unwrap();
+            let almanac = Almanac::parse(text.as_bytes()).unwrap();
+            assert_eq!(almanac.to_string(), text.trim_end(), "{}", path);
+        }
+    }
+
+    #[test]
+    fn to_json_works() {
+        let text = "seeds: 1 2\n\nseed-to-soil map:\n50 98 2\n52 50 48\n\nsoil-to-\"rock\" map:";
+        let almanac = Almanac::parse(text.as_bytes()).unwrap();
+
+        assert_eq!(
+            almanac.to_json(),
+            concat!(
+                "{\n",
+                "  \"seeds\":[1,2],\n",
+                "  \"chain\":[\"seed\",\"soil\",\"\\\"rock\\\"\"],\n",
+                "  \"maps\":[\n",
+                "    {\"source\":\"seed\",\"destination\":\"soil\",\"lines\":[",
+                "{\"destination_start\":50,\"source_start\":98,\"length\":2},",
+                "{\"destination_start\":52,\"source_start\":50,\"length\":48}]},\n",
+                "    {\"source\":\"soil\",\"destination\":\"\\\"rock\\\"\",\"lines\":[]}\n",
+                "  ]\n",
+                "}\n"
+            )
+        );
+    }
+
+    #[test]
+    fn invalid_almanacs_are_reported() {
+        let text = "seeds: 1 2\n\nseed-to-soil map:\n50 98 2\n52 50 50\n\nsoil-to-fertilizer map:";
+
+        let error = Almanac::parse(text.as_bytes()).unwrap_err();
+        assert!(matches!(
+            &error,
+            AlmanacError::InvalidMap { header, issues }
+                if header == "seed-to-soil map:"
+                    && issues
+                        == &[MapIssue::Overlap {
+                            first: Some(5),
+                            second: Some(4),
+                            sources: 98..100
+                        }]
+        ));
+        assert_eq!(
+            error.to_string(),
+            "invalid map \"seed-to-soil map:\"\n  line 5 and line 4 both map 98..100"
+        );
+
+        let almanac = Almanac::parse_with(text.as_bytes(), Overlaps::LastWins).unwrap();
+        assert_eq!(almanac.map("seed", "soil").unwrap().apply(99), 101);
+        assert_eq!(almanac.map("soil", "fertilizer").unwrap().apply(99), 99);
+        assert_eq!(
+            almanac.warnings(),
+            &[
+                (
+                    "seed-to-soil map:".to_string(),
+                    MapIssue::Overlap {
+                        first: Some(5),
+                        second: Some(4),
+                        sources: 98..100
+                    }
+                ),
+                (
+                    "soil-to-fertilizer map:".to_string(),
+                    MapIssue::EmptySection
+                )
+            ]
+        );
+
+        for (text, line) in [
+            ("seed: 1 2", 1),
+            ("seeds: 1 x", 1),
+            ("seeds: 1 2\n\n1 2 3", 3),
+            ("seeds: 1 2\n\nseed-to-soil map:\n1 2", 4),
+        ] {
+            assert!(
+                matches!(
+                    Almanac::parse(text.as_bytes()),
+                    Err(AlmanacError::InvalidLine { line: l, .. }) if l == line
+                ),
+                "{}",
+                text
+            );
+        }
+
+        // seeds that don't pair up into ranges are still seeds, but have no seed ranges
+        let text = "seeds: 79 14 55\n\nseed-to-location map:\n50 98 2\n52 50 48";
+        let almanac = Almanac::parse(text.as_bytes()).unwrap();
+        assert_eq!(crate::lowest_location_of_seeds(&almanac).unwrap(), 14);
+        assert!(matches!(
+            crate::lowest_location_of_ranges(&almanac),
+            Err(AlmanacError::InvalidSeedRange {
+                start: 55,
+                length: None
+            })
+        ));
+
+        let almanac = Almanac::parse("seeds: 9223372036854775806 5".as_bytes()).unwrap();
+        assert_eq!(almanac.seeds(), &[9223372036854775806, 5]);
+        assert!(matches!(
+            almanac.seed_ranges(),
+            Err(AlmanacError::InvalidSeedRange {
+                start: 9223372036854775806,
+                length: Some(5)
+            })
+        ));
+    }
+
+    #[test]
+    fn malformed_maps_are_reported() {
+        // map lines with too many or unreadable numbers, or before any header, are invalid lines
+        for (text, line) in [
+            ("seeds: 1 2\n\nseed-to-soil map:\n1 2 3 4", 4),
+            ("seeds: 1 2\n\nseed-to-soil map:\n1 2 x", 4),
+            (
+                "seeds: 1 2\n\nseed-to-soil map:\n99999999999999999999 2 3",
+                4,
+            ),
+            ("seeds: 1 2\n50 98 2", 2),
+        ] {
+            assert!(
+                matches!(
+                    Almanac::parse(text.as_bytes()),
+                    Err(AlmanacError::InvalidLine { line: l, .. }) if l == line
+                ),
+                "{}",
+                text
+            );
+        }
+
+        // maps that don't chain together still parse, but have no chain
+        let text = "seeds: 1 2\n\nseed-to-soil map:\n50 98 2\n\nwater-to-light map:\n1 2 3";
+        let almanac = Almanac::parse(text.as_bytes()).unwrap();
+        assert_eq!(almanac.map("water", "light").unwrap().apply(2), 1);
+        assert!(matches!(
+            almanac.chain(),
+            Err(AlmanacError::NoPath { from, to }) if from == "seed" && to == "light"
+        ));
+        assert!(almanac.to_json().contains("\"chain\":null,"));
+
+        assert!(matches!(
+            Almanac::parse("seeds: 1 2\n\nseed-to-soil\n50 98 2".as_bytes()),
+            Err(AlmanacError::MalformedHeader(header)) if header == "seed-to-soil"
+        ));
+
+        // a range that runs past the largest number is reported rather than wrapping around
+        let text = "seeds: 1 2\n\nseed-to-soil map:\n0 9223372036854775807 2";
+        assert!(matches!(
+            Almanac::parse(text.as_bytes()),
+            Err(AlmanacError::InvalidMap { issues, .. })
+                if issues == [MapIssue::Overflow { line: Some(4) }]
+        ));
+    }
+}
@@ -0,0 +1,391 @@
+use std::cmp;
+use std::fmt::Write;
+use std::ops::Range;
+
+use crate::{Almanac, AlmanacError, Map};
+
+/// How a diagram of an almanac's maps is drawn
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DiagramFormat {
+    /// plain text, fitted to a terminal of the given width
+    Text { width: usize },
+
+    /// an SVG image
+    Svg,
+}
+
+/// the width of the category labels at the start of each row of a text diagram
+const LABEL_WIDTH: usize = 12;
+
+/// the width of the bands in an SVG diagram, and the height of each row
+const SVG_WIDTH: f64 = 1000.0;
+const SVG_ROW: f64 = 60.0;
+const SVG_BAND: f64 = 20.0;
+
+/// A row of the diagram: a category, the map from it to the next category in the chain, and the
+/// value the winning seed has in it
+struct Stage<'a> {
+    category: &'a str,
+    map: Option<&'a Map>,
+    value: Option<i64>,
+}
+
+/// The keys drawn across the width of the diagram
+struct Axis {
+    start: i64,
+    end: i64,
+}
+
+impl Axis {
+    /// the axis that fits every range written in the almanac, and the seed ranges
+    fn of(almanac: &Almanac, seed_ranges: &[Range<i64>]) -> Self {
+        let ranges = almanac
+            .sections()
+            .iter()
+            .flat_map(|section| &section.lines)
+            .flat_map(|line| {
+                [
+                    line.source_start..line.source_start.saturating_add(line.length),
+                    line.destination_start..line.destination_start.saturating_add(line.length),
+                ]
+            })
+            .chain(seed_ranges.iter().cloned());
+
+        let (start, end) = ranges.fold((i64::MAX, i64::MIN), |(start, end), range| {
+            (cmp::min(start, range.start), cmp::max(end, range.end))
+        });
+
+        if start >= end {
+            return Self { start: 0, end: 1 };
+        }
+        Self { start, end }
+    }
+
+    /// the position of the key along a width, clamped to the width
+    fn position(&self, key: i64, width: f64) -> f64 {
+        let fraction = (key as f64 - self.start as f64) / (self.end as f64 - self.start as f64);
+        fraction.clamp(0.0, 1.0) * width
+    }
+
+    /// the column of the key in a band of the given number of columns
+    fn column(&self, key: i64, columns: usize) -> usize {
+        cmp::min(self.position(key, columns as f64) as usize, columns - 1)
+    }
+
+    /// the first key in the column
+    fn key(&self, column: usize, columns: usize) -> i64 {
+        let span = i128::from(self.end) - i128::from(self.start);
+        (i128::from(self.start) + span * column as i128 / columns as i128) as i64
+    }
+}
+
+/// draws each map along the almanac's chain as a band of its submaps, with arrows to the next
+/// stage, followed by the merged map. The seed ranges and the path of the seed that reaches the
+/// lowest location are highlighted. Fails if the maps don't form a chain, or the seeds don't
+/// pair up into ranges.
+pub fn render_diagram(almanac: &Almanac, format: DiagramFormat) -> Result<String, AlmanacError> {
+    let chain = almanac.chain()?;
+    let (Some(first), Some(last)) = (chain.first(), chain.last()) else {
+        return Ok(String::new());
+    };
+
+    let merged_map = almanac.compose(first, last)?;
+    let seed_ranges = almanac.seed_ranges()?;
+    let winning_seed = lowest_seed(&merged_map, &seed_ranges);
+
+    let mut value = winning_seed;
+    let stages: Vec<Stage> = chain
+        .iter()
+        .enumerate()
+        .map(|(i, category)| {
+            let map = chain
+                .get(i + 1)
+                .and_then(|next| almanac.map(category, next));
+            let stage = Stage {
+                category,
+                map,
+                value,
+            };
+            value = value.zip(map).map(|(value, map)| map.apply(value));
+            stage
+        })
+        .collect();
+
+    let merged = Stage {
+        category: "merged",
+        map: Some(&merged_map),
+        value: winning_seed,
+    };
+
+    let axis = Axis::of(almanac, &seed_ranges);
+
+    let diagram = match format {
+        DiagramFormat::Text { width } => render_text(&axis, &seed_ranges, &stages, &merged, width),
+        DiagramFormat::Svg => render_svg(&axis, &seed_ranges, &stages, &merged),
+    };
+    Ok(diagram)
+}
+
+/// the seed in the ranges that the map sends to the lowest location
+fn lowest_seed(map: &Map, seed_ranges: &[Range<i64>]) -> Option<i64> {
+    seed_ranges
+        .iter()
+        .flat_map(|range| map.intersecting_submaps(range.start, range.end))
+        .min_by_key(|sm| sm.destination_range().start)
+        .map(|sm| sm.source_start)
+}
+
+fn render_text(
+    axis: &Axis,
+    seed_ranges: &[Range<i64>],
+    stages: &[Stage],
+    merged: &Stage,
+    width: usize,
+) -> String {
+    let columns = cmp::max(width.saturating_sub(LABEL_WIDTH + 1), 10);
+    let mut out = String::new();
+    let mut row = |label: &str, band: Vec<char>| {
+        let band: String = band.into_iter().collect();
+        let label: String = label.chars().take(LABEL_WIDTH).collect();
+        writeln!(
+            out,
+            "{:<width$} {}",
+            label,
+            band.trim_end(),
+            width = LABEL_WIDTH
+        )
+        .unwrap();
+    };
+
+    let mut seeds = vec![' '; columns];
+    for range in seed_ranges.iter().filter(|range| !range.is_empty()) {
+        let (start, end) = (
+            axis.column(range.start, columns),
+            axis.column(range.end - 1, columns),
+        );
+        seeds[start..=end].fill('#');
+    }
+    row("seeds", seeds);
+
+    for stage in stages.iter().chain([merged]) {
+        row(stage.category, text_band(axis, stage, columns));
+
+        // mark where the start of each submap that moves its keys lands in the next stage
+        if let (Some(map), false) = (stage.map, std::ptr::eq(stage, merged)) {
+            let mut arrows = vec![' '; columns];
+            for sm in map
+                .submaps()
+                .iter()
+                .filter(|sm| sm.destination_difference != 0)
+            {
+                let start = sm.destination_range().start;
+                if (axis.start..axis.end).contains(&start) {
+                    arrows[axis.column(start, columns)] = 'v';
+                }
+            }
+            row("", arrows);
+        }
+    }
+
+    let path: Vec<String> = stages
+        .iter()
+        .filter_map(|stage| Some(format!("{} {}", stage.category, stage.value?)))
+        .collect();
+    if !path.is_empty() {
+        writeln!(out, "lowest: {}", path.join(" -> ")).unwrap();
+    }
+
+    out
+}
+
+/// a stage's submaps across the axis: '=' for keys left unchanged, '>' and '<' for keys moved up
+/// and down, '|' where a submap starts and '*' for the winning path
+fn text_band(axis: &Axis, stage: &Stage, columns: usize) -> Vec<char> {
+    let mut band = vec!['-'; columns];
+
+    if let Some(map) = stage.map {
+        for (column, c) in band.iter_mut().enumerate() {
+            let difference = map
+                .seek(axis.key(column, columns))
+                .map_or(0, |sm| sm.destination_difference);
+            *c = match difference.signum() {
+                1 => '>',
+                -1 => '<',
+                _ => '=',
+            };
+        }
+        for sm in map.submaps() {
+            if axis.start < sm.source_start && sm.source_start < axis.end {
+                band[axis.column(sm.source_start, columns)] = '|';
+            }
+        }
+    }
+
+    if let Some(value) = stage.value {
+        band[axis.column(value, columns)] = '*';
+    }
+
+    band
+}
+
+fn render_svg(axis: &Axis, seed_ranges: &[Range<i64>], stages: &[Stage], merged: &Stage) -> String {
+    let label_width = 120.0;
+    let x = |key: i64| label_width + axis.position(key, SVG_WIDTH);
+    let rows = stages.len() + 2;
+    let mut out = String::new();
+
+    writeln!(
+        out,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" font-family=\"monospace\" font-size=\"12\">",
+        label_width + SVG_WIDTH + 10.0,
+        rows as f64 * SVG_ROW
+    )
+    .unwrap();
+
+    let label = |out: &mut String, text: &str, y: f64| {
+        writeln!(
+            out,
+            "  <text x=\"4\" y=\"{}\">{}</text>",
+            y + 14.0,
+            xml_escape(text)
+        )
+        .unwrap();
+    };
+
+    label(&mut out, "seeds", 0.0);
+    for range in seed_ranges.iter().filter(|range| !range.is_empty()) {
+        writeln!(
+            out,
+            "  <rect x=\"{:.1}\" y=\"0\" width=\"{:.1}\" height=\"{}\" fill=\"#4caf50\"/>",
+            x(range.start),
+            (x(range.end) - x(range.start)).max(1.0),
+            SVG_BAND
+        )
+        .unwrap();
+    }
+
+    let mut path = Vec::new();
+    for (i, stage) in stages.iter().chain([merged]).enumerate() {
+        let y = (i + 1) as f64 * SVG_ROW;
+        label(&mut out, stage.category, y);
+
+        for sm in stage.map.iter().flat_map(|map| map.submaps()) {
+            let (start, end) = (
+                cmp::max(sm.source_start, axis.start),
+                cmp::min(sm.source_end, axis.end),
+            );
+            if start >= end {
+                continue;
+            }
+
+            let fill = match sm.destination_difference.signum() {
+                1 => "#5b8fd8",
+                -1 => "#e39b5b",
+                _ => "#dddddd",
+            };
+            writeln!(
+                out,
+                "  <rect x=\"{:.1}\" y=\"{}\" width=\"{:.1}\" height=\"{}\" fill=\"{}\" stroke=\"white\"/>",
+                x(start),
+                y,
+                (x(end) - x(start)).max(1.0),
+                SVG_BAND,
+                fill
+            )
+            .unwrap();
+
+            // an arrow from the keys to where they land in the next stage
+            if sm.destination_difference != 0 && i + 1 < stages.len() {
+                let destination =
+                    start + sm.destination_difference..end + sm.destination_difference;
+                writeln!(
+                    out,
+                    "  <polygon points=\"{:.1},{} {:.1},{} {:.1},{} {:.1},{}\" fill=\"{}\" fill-opacity=\"0.3\"/>",
+                    x(start),
+                    y + SVG_BAND,
+                    x(end),
+                    y + SVG_BAND,
+                    x(destination.end),
+                    y + SVG_ROW,
+                    x(destination.start),
+                    y + SVG_ROW,
+                    fill
+                )
+                .unwrap();
+            }
+        }
+        if stage.map.is_none() {
+            writeln!(
+                out,
+                "  <line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"#999999\"/>",
+                label_width,
+                y + SVG_BAND / 2.0,
+                label_width + SVG_WIDTH,
+                y + SVG_BAND / 2.0
+            )
+            .unwrap();
+        }
+
+        if let (Some(value), false) = (stage.value, std::ptr::eq(stage, merged)) {
+            path.push(format!("{:.1},{}", x(value), y + SVG_BAND / 2.0));
+        }
+    }
+
+    if !path.is_empty() {
+        writeln!(
+            out,
+            "  <polyline points=\"{}\" fill=\"none\" stroke=\"red\" stroke-width=\"2\"/>",
+            path.join(" ")
+        )
+        .unwrap();
+    }
+
+    out.push_str("</svg>\n");
+    out
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    fn test_almanac() -> Almanac {
+        Almanac::parse(fs::read_to_string("test_input").unwrap().as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn text_diagram_works() {
+        let diagram = render_diagram(&test_almanac(), DiagramFormat::Text { width: 80 }).unwrap();
+        let lines: Vec<&str> = diagram.lines().collect();
+
+        // seeds, a band and an arrow row for each map, location, merged and the path
+        assert_eq!(lines.len(), 1 + 7 * 2 + 1 + 1 + 1);
+        assert!(lines[..17].iter().all(|line| line.chars().count() <= 80));
+        assert!(lines[0].starts_with("seeds") && lines[0].contains('#'));
+        assert!(lines[1].starts_with("seed ") && lines[1].contains('*'));
+        assert!(lines[16].starts_with("merged"));
+        assert_eq!(
+            lines[17],
+            "lowest: seed 82 -> soil 84 -> fertilizer 84 -> water 84 -> light 77 -> \
+             temperature 45 -> humidity 46 -> location 46"
+        );
+    }
+
+    #[test]
+    fn svg_diagram_works() {
+        let diagram = render_diagram(&test_almanac(), DiagramFormat::Svg).unwrap();
+
+        assert!(diagram.starts_with("<svg"));
+        assert!(diagram.ends_with("</svg>\n"));
+        assert_eq!(diagram.matches("<polyline").count(), 1);
+        assert_eq!(diagram.matches("<text").count(), 10);
+        assert!(diagram.contains("fill=\"#4caf50\""));
+    }
+}
@@ -0,0 +1,124 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+use crate::{MapIssue, NotInjective};
+
+#[derive(Debug)]
+pub enum AlmanacError {
+    Io(io::Error),
+
+    /// a seeds line or map line that isn't a list of numbers in the right place. Lines start
+    /// from 1.
+    InvalidLine {
+        line: usize,
+        text: String,
+    },
+
+    /// a header that isn't of the form "source-to-destination map:"
+    MalformedHeader(String),
+
+    /// more than one map between the same two categories
+    DuplicateMap {
+        source: String,
+        destination: String,
+    },
+
+    /// a category that no map header mentions
+    UnknownCategory(String),
+
+    /// no chain of maps leads from one category to the other
+    NoPath {
+        from: String,
+        to: String,
+    },
+
+    /// a map whose ranges are invalid, with every issue that stops it being built
+    InvalidMap {
+        header: String,
+        issues: Vec<MapIssue>,
+    },
+
+    /// a map can't be inverted because more than one source maps to the same destination
+    NotInjective(NotInjective<i64>),
+
+    /// a seed range whose end can't be represented, or a last seed with no length
+    InvalidSeedRange {
+        start: i64,
+        length: Option<i64>,
+    },
+
+    /// the seeds line has no seeds, or only empty seed ranges, so no seed reaches a location
+    NoSeeds,
+}
+
+impl fmt::Display for AlmanacError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AlmanacError::Io(e) => write!(f, "failed to read almanac: {}", e),
+            AlmanacError::InvalidLine { line, text } => {
+                write!(f, "line {}: can't read \"{}\"", line, text)
+            }
+            AlmanacError::MalformedHeader(header) => {
+                write!(
+                    f,
+                    "expected a header like \"seed-to-soil map:\", found \"{}\"",
+                    header
+                )
+            }
+            AlmanacError::DuplicateMap {
+                source,
+                destination,
+            } => write!(f, "more than one {}-to-{} map", source, destination),
+            AlmanacError::UnknownCategory(category) => {
+                write!(f, "no map mentions the category \"{}\"", category)
+            }
+            AlmanacError::NoPath { from, to } => {
+                write!(f, "no chain of maps leads from {} to {}", from, to)
+            }
+            AlmanacError::InvalidMap { header, issues } => {
+                write!(f, "invalid map \"{}\"", header)?;
+                for issue in issues {
+                    write!(f, "\n  {}", issue)?;
+                }
+                Ok(())
+            }
+            AlmanacError::NotInjective(e) => write!(f, "map can't be inverted: {}", e),
+            AlmanacError::InvalidSeedRange {
+                start,
+                length: Some(length),
+            } => write!(
+                f,
+                "the seed range from {} of length {} ends past the largest number",
+                start, length
+            ),
+            AlmanacError::InvalidSeedRange {
+                start,
+                length: None,
+            } => write!(f, "the seed range from {} has no length", start),
+            AlmanacError::NoSeeds => write!(f, "no seeds to find the lowest location of"),
+        }
+    }
+}
+
+impl Error for AlmanacError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            AlmanacError::Io(e) => Some(e),
+            AlmanacError::NotInjective(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for AlmanacError {
+    fn from(e: io::Error) -> Self {
+        AlmanacError::Io(e)
+    }
+}
+
+impl From<NotInjective<i64>> for AlmanacError {
+    fn from(e: NotInjective<i64>) -> Self {
+        AlmanacError::NotInjective(e)
+    }
+}
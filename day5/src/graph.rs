@@ -0,0 +1,193 @@
+use std::collections::{BTreeSet, HashMap, VecDeque};
+
+use crate::{AlmanacError, Map};
+
+/// The categories of an almanac and the maps between them. Each map header such as
+/// "seed-to-soil map:" is an edge from its source category to its destination category.
+#[derive(Debug, Default)]
+pub struct CategoryGraph {
+    /// the map for each (source, destination) edge
+    maps: HashMap<(String, String), Map>,
+
+    /// the destinations reachable in one map from each source, in the order the maps were added
+    edges: HashMap<String, Vec<String>>,
+}
+
+impl CategoryGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// adds the map under a header such as "seed-to-soil map:"
+    pub fn add_map(&mut self, header: &str, map: Map) -> Result<(), AlmanacError> {
+        let (source, destination) = parse_header(header)
+            .ok_or_else(|| AlmanacError::MalformedHeader(header.to_string()))?;
+        let key = (source.to_string(), destination.to_string());
+
+        if self.maps.contains_key(&key) {
+            return Err(AlmanacError::DuplicateMap {
+                source: key.0,
+                destination: key.1,
+            });
+        }
+
+        self.edges
+            .entry(key.0.clone())
+            .or_default()
+            .push(key.1.clone());
+        self.maps.insert(key, map);
+
+        Ok(())
+    }
+
+    /// all the categories that appear in a header, in alphabetical order
+    pub fn categories(&self) -> BTreeSet<&str> {
+        self.maps
+            .keys()
+            .flat_map(|(source, destination)| [source.as_str(), destination.as_str()])
+            .collect()
+    }
+
+    pub fn map(&self, source: &str, destination: &str) -> Option<&Map> {
+        self.maps
+            .get(&(source.to_string(), destination.to_string()))
+    }
+
+    /// finds the shortest chain of categories leading from one category to the other, including
+    /// both ends
+    pub fn path(&self, from: &str, to: &str) -> Result<Vec<String>, AlmanacError> {
+        let categories = self.categories();
+        for category in [from, to] {
+            if !categories.contains(category) {
+                return Err(AlmanacError::UnknownCategory(category.to_string()));
+            }
+        }
+
+        // breadth first search, remembering the category each category was reached from
+        let mut reached_from: HashMap<&str, &str> = HashMap::new();
+        let mut queue = VecDeque::from([from]);
+
+        while let Some(category) = queue.pop_front() {
+            if category == to {
+                let mut path = vec![to.to_string()];
+                let mut current = to;
+                while current != from {
+                    current = reached_from[current];
+                    path.push(current.to_string());
+                }
+                path.reverse();
+                return Ok(path);
+            }
+
+            for next in self.edges.get(category).into_iter().flatten() {
+                if next != from && !reached_from.contains_key(next.as_str()) {
+                    reached_from.insert(next, category);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        Err(AlmanacError::NoPath {
+            from: from.to_string(),
+            to: to.to_string(),
+        })
+    }
+
+    /// merges the maps along the path from one category to the other into a single map
+    pub fn compose(&self, from: &str, to: &str) -> Result<Map, AlmanacError> {
+        let path = self.path(from, to)?;
+
+        let merged = path
+            .windows(2)
+            .map(|pair| &self.maps[&(pair[0].clone(), pair[1].clone())])
+            .fold(Map::identity(), |merged, map| merged.compose(map));
+
+        Ok(merged)
+    }
+}
+
+/// splits a header such as "seed-to-soil map:" into its source and destination categories
+pub(crate) fn parse_header(header: &str) -> Option<(&str, &str)> {
+    let name = header.strip_suffix(" map:")?;
+    let (source, destination) = name.split_once("-to-")?;
+
+    if source.is_empty() || destination.is_empty() {
+        return None;
+    }
+
+    Some((source, destination))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MapBuilder, SubMap};
+
+    /// a map that shifts the range by the offset, and leaves everything else alone
+    fn shift(source_start: i64, length: i64, offset: i64) -> Map {
+        let mut builder = MapBuilder::new();
+        builder.add(SubMap::new(source_start, source_start + offset, length));
+        builder.build().unwrap()
+    }
+
+    #[test]
+    fn parse_header_works() {
+        assert_eq!(parse_header("seed-to-soil map:"), Some(("seed", "soil")));
+        assert_eq!(
+            parse_header("light-to-temperature map:"),
+            Some(("light", "temperature"))
+        );
+        assert_eq!(parse_header("seed-to-soil"), None);
+        assert_eq!(parse_header("-to-soil map:"), None);
+    }
+
+    #[test]
+    fn category_graph_works() {
+        let mut graph = CategoryGraph::new();
+        graph
+            .add_map("seed-to-soil map:", shift(0, 10, 100))
+            .unwrap();
+        graph
+            .add_map("soil-to-water map:", shift(100, 5, 1000))
+            .unwrap();
+        graph
+            .add_map("water-to-location map:", shift(0, 1, 1))
+            .unwrap();
+        graph
+            .add_map("seed-to-water map:", shift(0, 10, 7))
+            .unwrap();
+
+        // the direct seed-to-water map is shorter than going through soil
+        assert_eq!(graph.path("seed", "water").unwrap(), vec!["seed", "water"]);
+        assert_eq!(
+            graph.path("soil", "location").unwrap(),
+            vec!["soil", "water", "location"]
+        );
+
+        let soil_to_water = graph.compose("soil", "water").unwrap();
+        assert_eq!(soil_to_water.apply(102), 1102);
+        assert_eq!(soil_to_water.apply(105), 105);
+
+        let seed_to_location = graph.compose("seed", "location").unwrap();
+        assert_eq!(seed_to_location.apply(3), 10);
+
+        assert_eq!(graph.compose("seed", "seed").unwrap().apply(3), 3);
+
+        assert!(matches!(
+            graph.path("location", "seed"),
+            Err(AlmanacError::NoPath { .. })
+        ));
+        assert!(matches!(
+            graph.path("seed", "humidity"),
+            Err(AlmanacError::UnknownCategory(c)) if c == "humidity"
+        ));
+        assert!(matches!(
+            graph.add_map("seed-to-soil map:", shift(0, 1, 1)),
+            Err(AlmanacError::DuplicateMap { .. })
+        ));
+        assert!(matches!(
+            graph.add_map("seed to soil map:", shift(0, 1, 1)),
+            Err(AlmanacError::MalformedHeader(_))
+        ));
+    }
+}
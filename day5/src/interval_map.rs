@@ -0,0 +1,910 @@
+//! Piecewise-linear maps over integer keys.
+//!
+//! An [`IntervalMap`] splits every key into contiguous half-open ranges, and shifts the keys in
+//! each range by that range's offset. Keys that no range was given for are left unchanged.
+//!
+//! ```
+//! use day5::{IntervalMap, MapBuilder, SubMap};
+//!
+//! // 98 and 99 map to 50 and 51, 50..98 maps to 52..100
+//! let mut builder = MapBuilder::new();
+//! builder.add(SubMap::new(98, 50, 2));
+//! builder.add(SubMap::new(50, 52, 48));
+//! let map: IntervalMap<i64> = builder.build().unwrap();
+//!
+//! assert_eq!(map.apply(79), 81);
+//! assert_eq!(map.apply(99), 51);
+//! assert_eq!(map.apply(10), 10);
+//! assert_eq!(map.image_of_range(96..100), vec![50..52, 98..100]);
+//! ```
+
+use std::cmp;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::ops::{Add, Range, Sub};
+
+/// A signed integer type that can be used as the keys of an IntervalMap. Offsets between keys are
+/// stored in the same type.
+pub trait IntervalKey: Copy + Ord + fmt::Debug + Add<Output = Self> + Sub<Output = Self> {
+    const MIN: Self;
+    const MAX: Self;
+    const ZERO: Self;
+
+    fn saturating_add(self, rhs: Self) -> Self;
+    fn saturating_sub(self, rhs: Self) -> Self;
+    fn checked_add(self, rhs: Self) -> Option<Self>;
+    fn checked_sub(self, rhs: Self) -> Option<Self>;
+}
+
+macro_rules! impl_interval_key {
+    ($($t:ty),*) => {
+        $(
+            impl IntervalKey for $t {
+                const MIN: Self = <$t>::MIN;
+                const MAX: Self = <$t>::MAX;
+                const ZERO: Self = 0;
+
+                fn saturating_add(self, rhs: Self) -> Self {
+                    <$t>::saturating_add(self, rhs)
+                }
+
+                fn saturating_sub(self, rhs: Self) -> Self {
+                    <$t>::saturating_sub(self, rhs)
+                }
+
+                fn checked_add(self, rhs: Self) -> Option<Self> {
+                    <$t>::checked_add(self, rhs)
+                }
+
+                fn checked_sub(self, rhs: Self) -> Option<Self> {
+                    <$t>::checked_sub(self, rhs)
+                }
+            }
+        )*
+    };
+}
+
+impl_interval_key!(i32, i64, i128);
+
+/// A "submap" corresponds to a line in the map input
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct SubMap<K = i64> {
+    /// the start of the source range (inclusive)
+    pub source_start: K,
+
+    /// the end of the source range (exclusive)
+    pub source_end: K,
+
+    /// the number that can be added to the source to obtain the destination
+    pub destination_difference: K,
+}
+
+impl<K: IntervalKey> SubMap<K> {
+    /// a submap from a map line's numbers. The ends of the ranges aren't checked, so this
+    /// overflows if they can't be represented; use checked_new for numbers that weren't checked.
+    pub fn new(source_start: K, destination_start: K, length: K) -> Self {
+        SubMap {
+            source_start,
+            source_end: source_start + length,
+            destination_difference: destination_start - source_start,
+        }
+    }
+
+    /// like new, but returns None if the end of the source or destination range, or the
+    /// difference between them, can't be represented
+    pub fn checked_new(source_start: K, destination_start: K, length: K) -> Option<Self> {
+        destination_start.checked_add(length)?;
+
+        Some(SubMap {
+            source_start,
+            source_end: source_start.checked_add(length)?,
+            destination_difference: destination_start.checked_sub(source_start)?,
+        })
+    }
+
+    pub fn get_destination(&self, source: K) -> Option<K> {
+        if source < self.source_start || source >= self.source_end {
+            None
+        } else {
+            Some(source + self.destination_difference)
+        }
+    }
+
+    /// the range the source range is mapped to, clamped to the keys that can be represented
+    pub fn destination_range(&self) -> Range<K> {
+        self.source_start
+            .saturating_add(self.destination_difference)
+            ..self.source_end.saturating_add(self.destination_difference)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.source_start >= self.source_end
+    }
+}
+
+/// How MapBuilder::build treats submaps whose source ranges overlap
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Overlaps {
+    /// overlapping submaps are an error
+    #[default]
+    Reject,
+
+    /// keys in more than one submap are mapped by the submap added first
+    FirstWins,
+
+    /// keys in more than one submap are mapped by the submap added last
+    LastWins,
+}
+
+/// Something wrong with the submaps given to a MapBuilder. Lines are the ones given to
+/// MapBuilder::add_line, and are None for submaps given to MapBuilder::add.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MapIssue<K = i64> {
+    /// two submaps map some of the same sources. Only an error when overlaps are rejected.
+    Overlap {
+        first: Option<usize>,
+        second: Option<usize>,
+        sources: Range<K>,
+    },
+
+    /// a submap with a length of 0, which maps nothing and is ignored
+    ZeroLength { line: Option<usize> },
+
+    /// a submap with a negative length
+    NegativeLength { line: Option<usize> },
+
+    /// the end of a submap's source or destination range is too large for the key type
+    Overflow { line: Option<usize> },
+
+    /// no submaps were added, so the map leaves every key unchanged
+    EmptySection,
+}
+
+impl<K> MapIssue<K> {
+    /// whether building with the overlap policy fails because of this issue
+    pub fn is_fatal(&self, overlaps: Overlaps) -> bool {
+        match self {
+            MapIssue::Overlap { .. } => overlaps == Overlaps::Reject,
+            MapIssue::NegativeLength { .. } | MapIssue::Overflow { .. } => true,
+            MapIssue::ZeroLength { .. } | MapIssue::EmptySection => false,
+        }
+    }
+}
+
+impl<K: fmt::Debug> fmt::Display for MapIssue<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let describe = |line: &Option<usize>| match line {
+            Some(line) => format!("line {}", line),
+            None => "a submap".to_string(),
+        };
+
+        match self {
+            MapIssue::Overlap {
+                first,
+                second,
+                sources,
+            } => write!(
+                f,
+                "{} and {} both map {:?}",
+                describe(first),
+                describe(second),
+                sources
+            ),
+            MapIssue::ZeroLength { line } => write!(f, "{} has a length of 0", describe(line)),
+            MapIssue::NegativeLength { line } => {
+                write!(f, "{} has a negative length", describe(line))
+            }
+            MapIssue::Overflow { line } => {
+                write!(f, "{} reaches past the largest key", describe(line))
+            }
+            MapIssue::EmptySection => write!(f, "the map has no ranges"),
+        }
+    }
+}
+
+/// the issues found by a MapBuilder
+type Issues<K> = Vec<MapIssue<K>>;
+
+/// A submap as it was added to a MapBuilder
+#[derive(PartialEq, Debug)]
+struct Entry<K> {
+    submap: SubMap<K>,
+    line: Option<usize>,
+}
+
+/// An intermediate representation of an IntervalMap. Build up this struct first and then call the
+/// build method to get an IntervalMap.
+#[derive(PartialEq, Debug)]
+pub struct MapBuilder<K = i64> {
+    entries: Vec<Entry<K>>,
+
+    /// the lines that were left out because their ranges can't be represented
+    overflows: Vec<Option<usize>>,
+
+    overlaps: Overlaps,
+}
+
+impl<K: IntervalKey> Default for MapBuilder<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: IntervalKey> MapBuilder<K> {
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            overflows: Vec::new(),
+            overlaps: Overlaps::default(),
+        }
+    }
+
+    /// sets how overlapping submaps are treated
+    pub fn overlaps(mut self, overlaps: Overlaps) -> Self {
+        self.overlaps = overlaps;
+        self
+    }
+
+    /// adds a submap that has already been made, so its ranges were checked, or not, by the
+    /// SubMap constructor it came from. Use add_line for numbers that weren't checked.
+    pub fn add(&mut self, submap: SubMap<K>) {
+        self.entries.push(Entry { submap, line: None });
+    }
+
+    /// adds the submap on a map line such as "50 98 2", remembering its line number for issues
+    pub fn add_line(&mut self, line: usize, destination_start: K, source_start: K, length: K) {
+        match SubMap::checked_new(source_start, destination_start, length) {
+            Some(submap) => self.entries.push(Entry {
+                submap,
+                line: Some(line),
+            }),
+            None => self.overflows.push(Some(line)),
+        }
+    }
+
+    /// every issue with the submaps added so far, whether or not it stops the map being built
+    pub fn issues(&self) -> Vec<MapIssue<K>> {
+        let mut issues: Vec<MapIssue<K>> = self
+            .overflows
+            .iter()
+            .map(|&line| MapIssue::Overflow { line })
+            .collect();
+
+        for Entry { submap, line } in &self.entries {
+            let line = *line;
+            if submap.source_end == submap.source_start {
+                issues.push(MapIssue::ZeroLength { line });
+            } else if submap.source_end < submap.source_start {
+                issues.push(MapIssue::NegativeLength { line });
+            }
+        }
+
+        let mut sorted: Vec<&Entry<K>> = self
+            .entries
+            .iter()
+            .filter(|entry| !entry.submap.is_empty())
+            .collect();
+        sorted.sort_by_key(|entry| entry.submap.source_start);
+
+        // compare each submap with the one before it that reaches furthest
+        let mut furthest: Option<&Entry<K>> = None;
+        for entry in sorted {
+            if let Some(previous) = furthest {
+                if entry.submap.source_start < previous.submap.source_end {
+                    issues.push(MapIssue::Overlap {
+                        first: previous.line,
+                        second: entry.line,
+                        sources: entry.submap.source_start
+                            ..cmp::min(entry.submap.source_end, previous.submap.source_end),
+                    });
+                }
+            }
+            if furthest.is_none_or(|previous| previous.submap.source_end < entry.submap.source_end)
+            {
+                furthest = Some(entry);
+            }
+        }
+
+        if self.entries.is_empty() && self.overflows.is_empty() {
+            issues.push(MapIssue::EmptySection);
+        }
+
+        issues
+    }
+
+    /// sorts the submaps and fills the gaps between them, and either side of them, with ranges
+    /// that leave keys unchanged. Fails with the issues that are fatal under the overlap policy.
+    /// Zero length submaps are ignored, and a builder with no submaps builds the identity map.
+    pub fn build(self) -> Result<IntervalMap<K>, Vec<MapIssue<K>>> {
+        self.build_with_warnings().map(|(map, _)| map)
+    }
+
+    /// like build, but also returns the issues that didn't stop the map being built
+    pub fn build_with_warnings(self) -> Result<(IntervalMap<K>, Issues<K>), Issues<K>> {
+        let (fatal, warnings): (Vec<MapIssue<K>>, Vec<MapIssue<K>>) = self
+            .issues()
+            .into_iter()
+            .partition(|issue| issue.is_fatal(self.overlaps));
+        if !fatal.is_empty() {
+            return Err(fatal);
+        }
+
+        let mut submaps: Vec<SubMap<K>> = self.entries.into_iter().map(|e| e.submap).collect();
+        if self.overlaps == Overlaps::LastWins {
+            submaps.reverse();
+        }
+
+        // the ranges claimed so far by the submaps that win them, keyed by source start
+        let mut claimed: BTreeMap<K, SubMap<K>> = BTreeMap::new();
+
+        for submap in submaps.into_iter().filter(|submap| !submap.is_empty()) {
+            let overlapping: Vec<Range<K>> = claimed
+                .range(..submap.source_end)
+                .rev()
+                .take_while(|(_, other)| other.source_end > submap.source_start)
+                .map(|(_, other)| other.source_start..other.source_end)
+                .collect();
+
+            // claim whatever is left of the submap between the ranges already claimed
+            let mut start = submap.source_start;
+            for other in overlapping.into_iter().rev() {
+                if start < other.start {
+                    claimed.insert(
+                        start,
+                        SubMap {
+                            source_start: start,
+                            source_end: other.start,
+                            ..submap
+                        },
+                    );
+                }
+                start = cmp::max(start, other.end);
+            }
+            if start < submap.source_end {
+                claimed.insert(
+                    start,
+                    SubMap {
+                        source_start: start,
+                        ..submap
+                    },
+                );
+            }
+        }
+
+        Ok((IntervalMap::fill_gaps(claimed.into_values()), warnings))
+    }
+}
+
+/// A map from keys to keys made of contiguous submaps sorted by source range. The submaps cover
+/// every key from K::MIN up to, but not including, K::MAX.
+#[derive(PartialEq, Debug, Clone)]
+pub struct IntervalMap<K = i64> {
+    submaps: Vec<SubMap<K>>,
+}
+
+impl<K: IntervalKey> IntervalMap<K> {
+    /// a map that leaves every key unchanged
+    pub fn identity() -> Self {
+        Self {
+            submaps: vec![SubMap {
+                source_start: K::MIN,
+                source_end: K::MAX,
+                destination_difference: K::ZERO,
+            }],
+        }
+    }
+
+    /// makes a map from sorted, non-overlapping submaps by filling the gaps between them, and
+    /// either side of them, with ranges that leave keys unchanged
+    fn fill_gaps(submaps: impl IntoIterator<Item = SubMap<K>>) -> Self {
+        let mut map = Self {
+            submaps: Vec::new(),
+        };
+
+        for submap in submaps {
+            let last_end = map.submaps.last().map_or(K::MIN, |last| last.source_end);
+            if last_end < submap.source_start {
+                map.submaps.push(SubMap {
+                    source_start: last_end,
+                    source_end: submap.source_start,
+                    destination_difference: K::ZERO,
+                });
+            }
+
+            map.submaps.push(submap);
+        }
+
+        let last_end = map.submaps.last().map_or(K::MIN, |last| last.source_end);
+        map.submaps.push(SubMap {
+            source_start: last_end,
+            source_end: K::MAX,
+            destination_difference: K::ZERO,
+        });
+
+        map
+    }
+
+    /// the submaps in source order
+    pub fn submaps(&self) -> &[SubMap<K>] {
+        &self.submaps
+    }
+
+    /// maps a single key
+    pub fn apply(&self, source: K) -> K {
+        self.seek(source)
+            .and_then(|s| s.get_destination(source))
+            .unwrap_or(source)
+    }
+
+    /// takes a range start and range end and returns the part of each submap that overlaps the
+    /// range, clipped to the range, in source order
+    pub fn intersecting_submaps(
+        &self,
+        range_start: K,
+        range_end: K,
+    ) -> impl Iterator<Item = SubMap<K>> + '_ {
+        // the first submap that ends after the range starts is the first that can overlap it
+        let first = self
+            .submaps
+            .partition_point(|sm| sm.source_end <= range_start);
+
+        self.submaps[first..]
+            .iter()
+            .take_while(move |sm| sm.source_start < range_end)
+            .map(move |sm| SubMap {
+                source_start: cmp::max(range_start, sm.source_start),
+                source_end: cmp::min(range_end, sm.source_end),
+                ..*sm
+            })
+            .filter(|sm| !sm.is_empty())
+    }
+
+    /// takes two maps A -> B and B -> C and merges into a single A -> C map
+    pub fn compose(&self, other: &Self) -> Self {
+        let mut result = Self {
+            submaps: Vec::with_capacity(self.submaps.len() + other.submaps.len()),
+        };
+
+        for submap in self.submaps.iter().filter(|submap| !submap.is_empty()) {
+            // the destinations of a submap are contiguous, so they land in a run of neighbouring
+            // submaps of the other map. Find the first with a binary search, then walk forward.
+            let destination_start = submap.destination_range().start;
+            let mut other_idx = other.index_of(destination_start);
+            let mut start = submap.source_start;
+
+            while start < submap.source_end {
+                // keys past the end of the other map are left unchanged by it
+                let Some(other_submap) = other_idx.and_then(|idx| other.submaps.get(idx)) else {
+                    result.submaps.push(SubMap {
+                        source_start: start,
+                        ..*submap
+                    });
+                    break;
+                };
+
+                // shift other submap source end to correspond with current submap source
+                let source_end_other = other_submap
+                    .source_end
+                    .saturating_sub(submap.destination_difference);
+
+                let end = cmp::min(submap.source_end, source_end_other);
+
+                let dd = submap.destination_difference + other_submap.destination_difference;
+
+                result.submaps.push(SubMap {
+                    source_start: start,
+                    source_end: end,
+                    destination_difference: dd,
+                });
+
+                start = end;
+                other_idx = other_idx.map(|idx| idx + 1);
+            }
+        }
+
+        result
+    }
+
+    /// returns the submap that contains source
+    pub fn seek(&self, source: K) -> Option<&SubMap<K>> {
+        self.index_of(source).map(|idx| &self.submaps[idx])
+    }
+
+    /// returns the index of the submap that contains source
+    fn index_of(&self, source: K) -> Option<usize> {
+        // the last submap that starts at or before source is the only one that can contain it
+        let idx = self
+            .submaps
+            .partition_point(|submap| submap.source_start <= source)
+            .checked_sub(1)?;
+
+        (source < self.submaps[idx].source_end).then_some(idx)
+    }
+
+    /// returns the map from destinations back to sources. Destinations that no source maps to
+    /// are left unchanged. Fails if two source ranges map onto overlapping destination ranges,
+    /// since the overlap would have more than one inverse.
+    pub fn inverse(&self) -> Result<Self, NotInjective<K>> {
+        // each inverted submap remembers the source range it came from, for reporting overlaps
+        let mut inverted: Vec<(SubMap<K>, Range<K>)> = self
+            .submaps
+            .iter()
+            .filter(|submap| !submap.is_empty())
+            .map(|submap| {
+                let destination = submap.destination_range();
+                let inverted = SubMap {
+                    source_start: destination.start,
+                    source_end: destination.end,
+                    destination_difference: K::ZERO - submap.destination_difference,
+                };
+                (inverted, submap.source_start..submap.source_end)
+            })
+            .collect();
+        inverted.sort_by_key(|(submap, _)| submap.source_start);
+
+        if let Some(pair) = inverted
+            .windows(2)
+            .find(|pair| pair[0].0.source_end > pair[1].0.source_start)
+        {
+            let ((first, first_sources), (second, second_sources)) = (&pair[0], &pair[1]);
+            return Err(NotInjective {
+                first: first_sources.clone(),
+                second: second_sources.clone(),
+                destinations: second.source_start..cmp::min(first.source_end, second.source_end),
+            });
+        }
+
+        let mut map = Self::fill_gaps(inverted.into_iter().map(|(submap, _)| submap));
+        map.simplify();
+
+        Ok(map)
+    }
+
+    /// returns the sorted, non-overlapping ranges that the keys in the range map to
+    pub fn image_of_range(&self, range: Range<K>) -> Vec<Range<K>> {
+        union(self.image_pieces(range).collect())
+    }
+
+    /// returns the sorted, non-overlapping ranges that the keys from start up to, but not
+    /// including, end map to
+    pub fn map_range(&self, start: K, end: K) -> Vec<Range<K>> {
+        self.image_of_range(start..end)
+    }
+
+    /// returns the sorted, non-overlapping ranges that the keys in any of the ranges map to. The
+    /// ranges may overlap each other.
+    pub fn map_ranges(&self, ranges: &[Range<K>]) -> Vec<Range<K>> {
+        union(
+            ranges
+                .iter()
+                .flat_map(|range| self.image_pieces(range.clone()))
+                .collect(),
+        )
+    }
+
+    /// the destination ranges of the parts of each submap that lie in the range
+    fn image_pieces(&self, range: Range<K>) -> impl Iterator<Item = Range<K>> + '_ {
+        self.intersecting_submaps(range.start, range.end)
+            .map(|submap| submap.destination_range())
+    }
+
+    /// returns the sorted, non-overlapping ranges of keys that map into the range
+    pub fn preimage_of_range(&self, range: Range<K>) -> Vec<Range<K>> {
+        let pieces = self
+            .submaps
+            .iter()
+            .filter_map(|submap| {
+                let start = cmp::max(
+                    range.start.saturating_sub(submap.destination_difference),
+                    submap.source_start,
+                );
+                let end = cmp::min(
+                    range.end.saturating_sub(submap.destination_difference),
+                    submap.source_end,
+                );
+                (start < end).then_some(start..end)
+            })
+            .collect();
+
+        union(pieces)
+    }
+
+    /// joins neighbouring submaps with the same difference and drops empty submaps, without
+    /// changing what the map does
+    pub fn simplify(&mut self) {
+        let mut simplified: Vec<SubMap<K>> = Vec::with_capacity(self.submaps.len());
+
+        for submap in self.submaps.drain(..).filter(|submap| !submap.is_empty()) {
+            match simplified.last_mut() {
+                Some(last) if last.destination_difference == submap.destination_difference => {
+                    last.source_end = submap.source_end;
+                }
+                _ => simplified.push(submap),
+            }
+        }
+
+        self.submaps = simplified;
+    }
+}
+
+/// Two source ranges of a map that map onto overlapping destination ranges
+#[derive(Debug, Clone, PartialEq)]
+pub struct NotInjective<K> {
+    pub first: Range<K>,
+    pub second: Range<K>,
+
+    /// the destinations that both source ranges map onto
+    pub destinations: Range<K>,
+}
+
+impl<K: fmt::Debug> fmt::Display for NotInjective<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "sources {:?} and {:?} both map onto {:?}",
+            self.first, self.second, self.destinations
+        )
+    }
+}
+
+impl<K: fmt::Debug> Error for NotInjective<K> {}
+
+/// sorts the ranges and joins the ones that overlap or touch
+fn union<K: IntervalKey>(mut ranges: Vec<Range<K>>) -> Vec<Range<K>> {
+    ranges.sort_by_key(|range| range.start);
+
+    let mut joined: Vec<Range<K>> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match joined.last_mut() {
+            Some(last) if range.start <= last.end => last.end = cmp::max(last.end, range.end),
+            _ => joined.push(range),
+        }
+    }
+
+    joined
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// the seed-to-soil map from the example almanac
+    fn seed_to_soil() -> IntervalMap<i64> {
+        let mut builder = MapBuilder::new();
+        builder.add(SubMap::new(98, 50, 2));
+        builder.add(SubMap::new(50, 52, 48));
+        builder.build().unwrap()
+    }
+
+    #[test]
+    fn apply_and_compose_work() {
+        let map = seed_to_soil();
+        assert_eq!(
+            [79, 14, 55, 13].map(|seed| map.apply(seed)),
+            [81, 14, 57, 13]
+        );
+
+        // the soil-to-fertilizer map from the example almanac
+        let mut builder = MapBuilder::new();
+        builder.add(SubMap::new(15, 0, 37));
+        builder.add(SubMap::new(52, 37, 2));
+        builder.add(SubMap::new(0, 39, 15));
+        let soil_to_fertilizer = builder.build().unwrap();
+
+        let composed = map.compose(&soil_to_fertilizer);
+        for seed in -5..110 {
+            assert_eq!(
+                composed.apply(seed),
+                soil_to_fertilizer.apply(map.apply(seed))
+            );
+        }
+    }
+
+    #[test]
+    fn lookups_work() {
+        // shuffled ranges of varied lengths, so composing jumps around the other map
+        let mut builder = MapBuilder::new();
+        let mut other = MapBuilder::new();
+        for i in 0..50 {
+            builder.add(SubMap::new(i * 10, (i * 37) % 50 * 10, 10));
+            other.add(SubMap::new(i * 7, 1000 - i * 7, 7));
+        }
+        let (map, other) = (builder.build().unwrap(), other.build().unwrap());
+        let composed = map.compose(&other);
+
+        for key in -20..520 {
+            let expected = map
+                .submaps()
+                .iter()
+                .find_map(|submap| submap.get_destination(key))
+                .unwrap_or(key);
+            assert_eq!(map.apply(key), expected);
+            assert_eq!(composed.apply(key), other.apply(map.apply(key)));
+        }
+        assert!(map.seek(i64::MAX).is_none());
+    }
+
+    #[test]
+    fn build_issues_work() {
+        let mut builder = MapBuilder::new();
+        builder.add_line(3, 50, 98, 2);
+        builder.add_line(4, 52, 50, 48);
+        builder.add_line(5, 0, 90, 10);
+        builder.add_line(6, 7, 7, 0);
+        builder.add_line(7, 0, i64::MAX - 1, 2);
+
+        assert_eq!(
+            builder.issues(),
+            vec![
+                MapIssue::Overflow { line: Some(7) },
+                MapIssue::ZeroLength { line: Some(6) },
+                MapIssue::Overlap {
+                    first: Some(4),
+                    second: Some(5),
+                    sources: 90..98
+                },
+                MapIssue::Overlap {
+                    first: Some(5),
+                    second: Some(3),
+                    sources: 98..100
+                },
+            ]
+        );
+        assert_eq!(
+            builder.build().unwrap_err(),
+            vec![
+                MapIssue::Overflow { line: Some(7) },
+                MapIssue::Overlap {
+                    first: Some(4),
+                    second: Some(5),
+                    sources: 90..98
+                },
+                MapIssue::Overlap {
+                    first: Some(5),
+                    second: Some(3),
+                    sources: 98..100
+                },
+            ]
+        );
+
+        // an empty section builds the identity map, with a warning
+        let builder = MapBuilder::<i64>::new();
+        assert_eq!(builder.issues(), vec![MapIssue::EmptySection]);
+        assert_eq!(
+            builder.build_with_warnings().unwrap(),
+            (IntervalMap::identity(), vec![MapIssue::EmptySection])
+        );
+
+        // issues that don't stop the map being built are returned alongside it
+        let mut builder = MapBuilder::new().overlaps(Overlaps::FirstWins);
+        builder.add_line(1, 50, 98, 2);
+        builder.add_line(2, 0, 99, 0);
+        builder.add_line(3, 60, 90, 10);
+        let (map, warnings) = builder.build_with_warnings().unwrap();
+        assert_eq!(map.apply(99), 51);
+        assert_eq!(
+            warnings,
+            vec![
+                MapIssue::ZeroLength { line: Some(2) },
+                MapIssue::Overlap {
+                    first: Some(3),
+                    second: Some(1),
+                    sources: 98..100
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn overlaps_resolve() {
+        let builder = |overlaps| {
+            let mut builder = MapBuilder::new().overlaps(overlaps);
+            builder.add(SubMap::new(0, 100, 10));
+            builder.add(SubMap::new(5, 200, 10));
+            builder.add(SubMap::new(2, 300, 2));
+            builder.build().unwrap()
+        };
+
+        let first_wins = builder(Overlaps::FirstWins);
+        assert_eq!(
+            [0, 3, 9, 10, 14, 15].map(|key| first_wins.apply(key)),
+            [100, 103, 109, 205, 209, 15]
+        );
+
+        let last_wins = builder(Overlaps::LastWins);
+        assert_eq!(
+            [0, 3, 4, 5, 14, 15].map(|key| last_wins.apply(key)),
+            [100, 301, 104, 200, 209, 15]
+        );
+    }
+
+    #[test]
+    fn intersecting_submaps_work() {
+        let map = seed_to_soil();
+
+        // 40..60 starts in the identity range below 50, which starts before it
+        assert_eq!(
+            map.intersecting_submaps(40, 60).collect::<Vec<_>>(),
+            vec![
+                SubMap {
+                    source_start: 40,
+                    source_end: 50,
+                    destination_difference: 0
+                },
+                SubMap {
+                    source_start: 50,
+                    source_end: 60,
+                    destination_difference: 2
+                },
+            ]
+        );
+        assert_eq!(
+            map.intersecting_submaps(60, 61).collect::<Vec<_>>(),
+            vec![SubMap {
+                source_start: 60,
+                source_end: 61,
+                destination_difference: 2
+            }]
+        );
+        assert_eq!(map.intersecting_submaps(99, 101).count(), 2);
+        assert_eq!(map.intersecting_submaps(60, 60).count(), 0);
+    }
+
+    #[test]
+    fn inverse_works() {
+        let map = seed_to_soil();
+        let inverse = map.inverse().unwrap();
+
+        for seed in -5..110 {
+            assert_eq!(inverse.apply(map.apply(seed)), seed);
+        }
+
+        // 0..10 and 10..15 both map onto 5..10
+        let mut builder = MapBuilder::new();
+        builder.add(SubMap::new(0, 0, 10));
+        builder.add(SubMap::new(10, 5, 5));
+        assert_eq!(
+            builder.build().unwrap().inverse(),
+            Err(NotInjective {
+                first: 0..10,
+                second: 10..15,
+                destinations: 5..10
+            })
+        );
+    }
+
+    #[test]
+    fn ranges_work() {
+        let map = seed_to_soil();
+
+        assert_eq!(map.image_of_range(40..60), vec![40..50, 52..62]);
+        assert_eq!(map.image_of_range(90..110), vec![50..52, 92..110]);
+        assert_eq!(map.preimage_of_range(50..53), vec![50..51, 98..100]);
+        assert_eq!(map.preimage_of_range(0..10), vec![0..10]);
+
+        assert_eq!(map.map_range(90, 110), map.image_of_range(90..110));
+        // 45..55 and 52..60 overlap, and 97..99 maps onto 99..100 and 50..51
+        assert_eq!(
+            map.map_ranges(&[52..60, 45..55, 97..99]),
+            vec![45..51, 52..62, 99..100]
+        );
+        assert!(map.map_ranges(&[]).is_empty());
+    }
+
+    #[test]
+    fn simplify_works() {
+        let mut builder = MapBuilder::new();
+        builder.add(SubMap::new(0, 5, 10));
+        builder.add(SubMap::new(10, 15, 10));
+        builder.add(SubMap::new(20, 20, 10));
+        let mut map = builder.build().unwrap();
+        let original = map.clone();
+
+        map.simplify();
+        assert_eq!(map.submaps().len(), 3);
+        for key in -5..40 {
+            assert_eq!(map.apply(key), original.apply(key));
+        }
+    }
+}
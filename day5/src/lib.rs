@@ -1,339 +1,317 @@
-use std::collections::HashMap;
+use std::cmp;
 use std::fs::File;
-use std::io::{BufRead, BufReader};
-use std::{cmp, io};
-
-pub fn pt1(path: &str) -> Result<i64, io::Error> {
-    let file = File::open(path)?;
-
-    let buffered = BufReader::new(file);
-
-    // create an iterator over the lines of the file
-    let mut lines_iter = buffered.lines().filter_map(|line_result| line_result.ok());
-
-    let seeds_line = lines_iter.next().unwrap();
-
-    let mut seeds_splits = seeds_line.split(' ');
-
-    seeds_splits.next();
-
-    let seeds: Vec<i64> = seeds_splits.map(|s| str::parse(s).unwrap()).collect();
-
-    let mut map_of_map_builders: HashMap<String, MapBuilder> = HashMap::new();
-
-    let mut current_header = String::from("");
-
-    for line in lines_iter {
-        if let Some(c) = line.chars().next() {
-            if c.is_digit(10) {
-                let mut nums_iter = line.split(' ').map(|s| str::parse(s).unwrap());
-                let destination_start = nums_iter.next().unwrap();
-                let source_start = nums_iter.next().unwrap();
-                let length = nums_iter.next().unwrap();
-                let map = map_of_map_builders.get_mut(&current_header).unwrap();
-                map.submaps
-                    .push(SubMap::new(source_start, destination_start, length));
-            } else {
-                map_of_map_builders.insert(line.clone(), MapBuilder::new());
-                current_header = line;
-            }
-        }
-    }
-
-    let mut map_of_maps: HashMap<String, Map> = HashMap::new();
-
-    for (k, v) in map_of_map_builders {
-        map_of_maps.insert(k, v.build());
-    }
+use std::io::BufReader;
+use std::ops::Range;
+
+mod almanac;
+mod diagram;
+mod error;
+mod graph;
+mod interval_map;
+
+pub use almanac::{Almanac, MapLine, MapSection};
+pub use diagram::{render_diagram, DiagramFormat};
+pub use error::AlmanacError;
+pub use graph::CategoryGraph;
+pub use interval_map::{
+    IntervalKey, IntervalMap, MapBuilder, MapIssue, NotInjective, Overlaps, SubMap,
+};
+
+/// A conversion from one category of numbers to another
+pub type Map = IntervalMap<i64>;
+
+pub fn pt1(path: &str) -> Result<i64, AlmanacError> {
+    lowest_location_of_seeds(&read_almanac(path)?)
+}
 
-    let merged_map = seed_location_map(map_of_maps);
+fn lowest_location_of_seeds(almanac: &Almanac) -> Result<i64, AlmanacError> {
+    let merged_map = almanac.compose("seed", "location")?;
 
-    let result = seeds
+    let result = almanac
+        .seeds()
         .iter()
-        .map(|s| merged_map.get_destination(*s))
+        .map(|s| merged_map.apply(*s))
         .min()
-        .unwrap();
+        .ok_or(AlmanacError::NoSeeds)?;
 
-    return Ok(result);
+    Ok(result)
 }
 
 /// At a high level, the approach is:
 /// 1. Merge all the maps into a single seed to location map
-/// 2. For each seed range, find the submaps in the map that intersect with the seed range.
-/// 3. For each of these submaps, find the location that corresponds to the lower bound of the submap's source range. If the lower bound of the submap's source range is lower than the lower bound of the seed range, use the location corresponding to the location's seed range instead.
+/// 2. For each seed range, find the parts of the submaps in the map that overlap the seed range.
+/// 3. Each part maps its seeds onto a contiguous range of locations, so the lowest location it reaches is the one its first seed maps to.
 /// 4. The answer should be the minimum of these locations.
-pub fn pt2(path: &str) -> Result<i64, io::Error> {
-    let file = File::open(path)?;
-
-    let buffered = BufReader::new(file);
-
-    // create an iterator over the lines of the file
-    let mut lines_iter = buffered.lines().filter_map(|line_result| line_result.ok());
+pub fn pt2(path: &str) -> Result<i64, AlmanacError> {
+    lowest_location_of_ranges(&read_almanac(path)?)
+}
 
-    // assume first line contains seed ranges
-    let seeds_line = lines_iter.next().unwrap();
+fn lowest_location_of_ranges(almanac: &Almanac) -> Result<i64, AlmanacError> {
+    let merged_map = almanac.compose("seed", "location")?;
 
-    let mut seeds_splits = seeds_line.split(' ');
+    let result = almanac
+        .seed_ranges()?
+        .into_iter()
+        .filter_map(|seed_range| {
+            merged_map
+                .intersecting_submaps(seed_range.start, seed_range.end)
+                .map(|sm| sm.destination_range().start)
+                .min()
+        })
+        .min()
+        .ok_or(AlmanacError::NoSeeds)?;
 
-    // leave off the "seeds:" part
-    seeds_splits.next();
+    Ok(result)
+}
 
-    let seeds: Vec<i64> = seeds_splits.map(|s| str::parse(s).unwrap()).collect();
+/// solves pt2 the other way round: inverts the seed to location map, then walks the locations up
+/// from the lowest until one of them comes from a seed in a seed range
+pub fn pt2_by_inverse(path: &str) -> Result<i64, AlmanacError> {
+    lowest_location_by_inverse(&read_almanac(path)?)
+}
 
-    let mut map_of_map_builders: HashMap<String, MapBuilder> = HashMap::new();
+fn lowest_location_by_inverse(almanac: &Almanac) -> Result<i64, AlmanacError> {
+    let seed_ranges = almanac.seed_ranges()?;
 
-    let mut current_header = String::from("");
+    let location_seed_map = almanac.compose("seed", "location")?.inverse()?;
 
-    for line in lines_iter {
-        if let Some(c) = line.chars().next() {
-            if c.is_digit(10) {
-                let mut nums_iter = line.split(' ').map(|s| str::parse(s).unwrap());
-                let destination_start = nums_iter.next().unwrap();
-                let source_start = nums_iter.next().unwrap();
-                let length = nums_iter.next().unwrap();
-                let map = map_of_map_builders.get_mut(&current_header).unwrap();
-                map.submaps
-                    .push(SubMap::new(source_start, destination_start, length));
-            } else {
-                map_of_map_builders.insert(line.clone(), MapBuilder::new());
-                current_header = line;
-            }
-        }
-    }
+    // inverse submaps are sorted by location, so the first one that reaches a seed range holds the
+    // lowest location
+    let result = location_seed_map
+        .submaps()
+        .iter()
+        .find_map(|sm| {
+            let seeds_reached = sm.destination_range();
+
+            seed_ranges
+                .iter()
+                .filter_map(|seed_range| {
+                    let start = cmp::max(seed_range.start, seeds_reached.start);
+                    let end = cmp::min(seed_range.end, seeds_reached.end);
+                    (start < end).then_some(start - sm.destination_difference)
+                })
+                .min()
+        })
+        .ok_or(AlmanacError::NoSeeds)?;
 
-    let mut map_of_maps: HashMap<String, Map> = HashMap::new();
+    Ok(result)
+}
 
-    for (k, v) in map_of_map_builders {
-        map_of_maps.insert(k, v.build());
-    }
+/// returns the ranges of seeds, of any value, that end up at a location below the given location
+pub fn seeds_reaching(path: &str, below: i64) -> Result<Vec<Range<i64>>, AlmanacError> {
+    let location_seed_map = category_graph(path)?
+        .compose("seed", "location")?
+        .inverse()?;
 
-    let merged_map = seed_location_map(map_of_maps);
+    Ok(location_seed_map.image_of_range(i64::MIN..below))
+}
 
-    println!("merged_map num submaps {}", merged_map.submaps.len());
+/// returns the sorted, merged ranges of every location reached from the seed ranges
+pub fn location_ranges(path: &str) -> Result<Vec<Range<i64>>, AlmanacError> {
+    let almanac = read_almanac(path)?;
 
-    // assumes there are an even number of seed numbers
-    let seed_ranges = seeds.chunks_exact(2);
+    let merged_map = almanac.compose("seed", "location")?;
 
-    let result = seed_ranges
-        .map(|seed_range| {
-            let &start = seed_range.get(0).unwrap();
-            let &length = seed_range.get(1).unwrap();
-            let end = start + length;
+    Ok(merged_map.map_ranges(&almanac.seed_ranges()?))
+}
 
-            merged_map
-                .intersecting_submaps(start, end)
-                .map(|sm| cmp::max(start, sm.source_start) + sm.destination_difference)
-                .min()
+/// counts how many numbers in the window lie in any of the sorted, non-overlapping ranges
+pub fn coverage(ranges: &[Range<i64>], window: Range<i64>) -> u64 {
+    ranges
+        .iter()
+        .map(|range| {
+            let start = cmp::max(range.start, window.start);
+            let end = cmp::min(range.end, window.end);
+            end.saturating_sub(start).max(0) as u64
         })
-        .filter_map(|x| x)
-        .min()
-        .unwrap();
+        .sum()
+}
 
-    return Ok(result);
+/// draws the maps of the almanac in the file, from the seeds to the lowest location
+pub fn diagram(path: &str, format: DiagramFormat) -> Result<String, AlmanacError> {
+    render_diagram(&read_almanac(path)?, format)
 }
 
-/// A "submap" corresponds to a line in the map input
-#[derive(PartialEq, Debug)]
-struct SubMap {
-    /// the start of the source range (inclusive)
-    source_start: i64,
+/// reads and parses the almanac in the file
+fn read_almanac(path: &str) -> Result<Almanac, AlmanacError> {
+    read_almanac_with(path, Overlaps::Reject)
+}
 
-    /// the end of the source range (exclusive)
-    source_end: i64,
+fn read_almanac_with(path: &str, overlaps: Overlaps) -> Result<Almanac, AlmanacError> {
+    let file = File::open(path)?;
 
-    /// the number that can be added to the source to obtain the destination
-    destination_difference: i64,
+    Almanac::parse_with(BufReader::new(file), overlaps)
 }
 
-impl SubMap {
-    fn new(source_start: i64, destination_start: i64, length: i64) -> Self {
-        SubMap {
-            source_start,
-            source_end: source_start + length,
-            destination_difference: destination_start - source_start,
-        }
-    }
-
-    fn get_destination(&self, source: i64) -> Option<i64> {
-        if source < self.source_start || source >= self.source_end {
-            return None;
-        } else {
-            return Some(source + self.destination_difference);
-        }
-    }
+/// reads the maps of an almanac into a graph of the categories they convert between
+pub fn category_graph(path: &str) -> Result<CategoryGraph, AlmanacError> {
+    category_graph_with(path, Overlaps::Reject)
 }
 
-/// An intermediate representation of a Map. Build up this struct first and then call the build method to get a Map.
-#[derive(PartialEq, Debug)]
-struct MapBuilder {
-    submaps: Vec<SubMap>,
+/// like category_graph, but resolves ranges that overlap within a map by the policy instead of
+/// rejecting them
+pub fn category_graph_with(path: &str, overlaps: Overlaps) -> Result<CategoryGraph, AlmanacError> {
+    Ok(read_almanac_with(path, overlaps)?.into_graph())
 }
 
-impl MapBuilder {
-    fn new() -> Self {
-        Self {
-            submaps: Vec::new(),
-        }
-    }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    fn build(mut self) -> Map {
-        self.submaps
-            .sort_by(|a, b| a.source_start.cmp(&b.source_start));
-
-        let mut map = Map {
-            submaps: Vec::new(),
-        };
-
-        let first_submap = self.submaps.get(0).unwrap();
-
-        map.submaps.push(SubMap {
-            source_start: i64::MIN,
-            source_end: first_submap.source_start,
-            destination_difference: 0,
-        });
-
-        for submap in self.submaps {
-            if let Some(last_submap) = map.submaps.last() {
-                // check for gap
-                if last_submap.source_end < submap.source_start {
-                    map.submaps.push(SubMap {
-                        source_start: last_submap.source_end,
-                        source_end: submap.source_start,
-                        destination_difference: 0,
-                    })
-                }
-            }
+    /// every almanac, with its pt1 and pt2 answers. The last three came from teammates.
+    const FIXTURES: [(&str, i64, i64); 5] = [
+        ("test_input", 35, 46),
+        ("input", 261668924, 24261545),
+        ("tonys_input", 1181555926, 37806486),
+        ("tims_input", 177942185, 69841803),
+        ("diggseys_input", 313045984, 20283860),
+    ];
 
-            map.submaps.push(submap);
+    #[test]
+    fn pt1_works() {
+        for (path, expected, _) in FIXTURES {
+            assert_eq!(pt1(path).unwrap(), expected, "{}", path);
         }
-
-        map.submaps.push(SubMap {
-            source_start: map.submaps.last().unwrap().source_end,
-            source_end: i64::MAX,
-            destination_difference: 0,
-        });
-
-        map
     }
-}
 
-#[derive(PartialEq, Debug)]
-struct Map {
-    submaps: Vec<SubMap>,
-}
+    #[test]
+    fn no_seeds_are_reported() {
+        let almanac =
+            Almanac::parse("seeds:\n\nseed-to-location map:\n2 5 3\n5 2 3".as_bytes()).unwrap();
+
+        assert!(matches!(
+            lowest_location_of_seeds(&almanac),
+            Err(AlmanacError::NoSeeds)
+        ));
+        assert!(matches!(
+            lowest_location_of_ranges(&almanac),
+            Err(AlmanacError::NoSeeds)
+        ));
+        assert!(matches!(
+            lowest_location_by_inverse(&almanac),
+            Err(AlmanacError::NoSeeds)
+        ));
+    }
 
-impl Map {
-    fn get_destination(&self, source: i64) -> i64 {
-        self.submaps
-            .iter()
-            .map(|s| s.get_destination(source))
-            .find_map(|d| d)
-            .unwrap_or(source)
+    #[test]
+    fn category_graph_works() {
+        let graph = category_graph("test_input").unwrap();
+
+        assert_eq!(
+            graph.path("seed", "location").unwrap(),
+            vec![
+                "seed",
+                "soil",
+                "fertilizer",
+                "water",
+                "light",
+                "temperature",
+                "humidity",
+                "location"
+            ]
+        );
+
+        // seed 79 is soil 81, fertilizer 81, water 81, light 74, temperature 78 and humidity 78
+        assert_eq!(graph.compose("soil", "humidity").unwrap().apply(81), 78);
+        assert_eq!(graph.compose("seed", "water").unwrap().apply(79), 81);
+        assert_eq!(graph.compose("seed", "light").unwrap().apply(79), 74);
     }
 
-    /// takes a range start and range end and returns all submaps that intersect with that range.
-    fn intersecting_submaps(
-        &self,
-        range_start: i64,
-        range_end: i64,
-    ) -> impl Iterator<Item = &SubMap> {
-        self.submaps
-            .iter()
-            .filter(move |sm| sm.source_start >= range_start && sm.source_start < range_end)
+    #[test]
+    fn pt2_works() {
+        for (path, _, expected) in FIXTURES {
+            assert_eq!(pt2(path).unwrap(), expected, "{}", path);
+        }
     }
 
-    /// takes two maps A -> B and B -> C and merges into a single A -> C map
-    fn merge(&self, other: &Self) -> Self {
-        let mut result = Self {
-            submaps: Vec::new(),
-        };
-
-        for submap in &self.submaps {
-            'inner: loop {
-                // continue from the end of the last submap added to result
-                let start = match result.submaps.last() {
-                    Some(sm) => sm.source_end,
-                    None => submap.source_start,
-                };
-
-                let destination_start = start.saturating_add(submap.destination_difference);
-
-                // find other submap that corresponds to current submap destination start
-                let other_submap = other
-                    .seek(destination_start)
-                    .expect(&format!("failed to find {} in other map", start));
-
-                // shift other submap source end to correspond with current submap source
-                let source_end_other = other_submap
-                    .source_end
-                    .saturating_sub(submap.destination_difference);
-
-                let end = cmp::min(submap.source_end, source_end_other);
-
-                let dd = submap.destination_difference + other_submap.destination_difference;
-
-                result.submaps.push(SubMap {
-                    source_start: start,
-                    source_end: end,
-                    destination_difference: dd,
-                });
-
-                // if current submap source end was used, move on to next submap
-                if end == submap.source_end {
-                    break 'inner;
-                }
-            }
+    #[test]
+    fn pt2_by_inverse_works() {
+        for (path, _, expected) in FIXTURES {
+            assert_eq!(pt2_by_inverse(path).unwrap(), expected, "{}", path);
         }
 
-        result
+        // seed ranges of length 0 hold no seeds to reach a location from
+        let almanac =
+            Almanac::parse("seeds: 79 0 55 0\n\nseed-to-location map:\n2 5 3\n5 2 3".as_bytes());
+        assert!(matches!(
+            lowest_location_by_inverse(&almanac.unwrap()),
+            Err(AlmanacError::NoSeeds)
+        ));
     }
 
-    /// returns the submap that contains source
-    fn seek(&self, source: i64) -> Option<&SubMap> {
-        for submap in &self.submaps {
-            if source >= submap.source_start && source < submap.source_end {
-                return Some(submap);
+    #[test]
+    fn intersecting_submaps_cover_seed_ranges() {
+        // the clipped overlaps of each seed range cover it exactly, and agree with the map
+        for (path, _, _) in FIXTURES {
+            let almanac = read_almanac(path).unwrap();
+            let merged_map = almanac.compose("seed", "location").unwrap();
+
+            for Range { start, end } in almanac.seed_ranges().unwrap() {
+                let overlaps: Vec<SubMap> = merged_map.intersecting_submaps(start, end).collect();
+
+                assert_eq!(overlaps.first().unwrap().source_start, start, "{}", path);
+                assert_eq!(overlaps.last().unwrap().source_end, end, "{}", path);
+                for pair in overlaps.windows(2) {
+                    assert_eq!(pair[0].source_end, pair[1].source_start, "{}", path);
+                }
+                for sm in overlaps {
+                    let seed = sm.source_start;
+                    assert_eq!(
+                        sm.get_destination(seed),
+                        Some(merged_map.apply(seed)),
+                        "{}",
+                        path
+                    );
+                }
             }
         }
-        None
     }
-}
-
-/// takes a map of maps, returns a seed -> location map
-fn seed_location_map(map_of_maps: HashMap<String, Map>) -> Map {
-    let seed_to_soil_map = map_of_maps.get("seed-to-soil map:").unwrap();
-    let soil_to_fertilizer_map = map_of_maps.get("soil-to-fertilizer map:").unwrap();
-    let fertilizer_to_water_map = map_of_maps.get("fertilizer-to-water map:").unwrap();
-    let water_to_light_map = map_of_maps.get("water-to-light map:").unwrap();
-    let light_to_temperature_map = map_of_maps.get("light-to-temperature map:").unwrap();
-    let temperature_to_humidity_map = map_of_maps.get("temperature-to-humidity map:").unwrap();
-    let humidity_to_location_map = map_of_maps.get("humidity-to-location map:").unwrap();
-
-    seed_to_soil_map
-        .merge(soil_to_fertilizer_map)
-        .merge(fertilizer_to_water_map)
-        .merge(water_to_light_map)
-        .merge(light_to_temperature_map)
-        .merge(temperature_to_humidity_map)
-        .merge(humidity_to_location_map)
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
 
     #[test]
-    fn pt1_works() {
-        assert_eq!(pt1("test_input").unwrap(), 35);
-        assert_eq!(pt1("input").unwrap(), 261668924);
+    fn location_ranges_work() {
+        // seeds 79..93 and 55..68
+        let locations = location_ranges("test_input").unwrap();
+        let seed_to_location = category_graph("test_input")
+            .unwrap()
+            .compose("seed", "location")
+            .unwrap();
+
+        let mut expected: Vec<i64> = (79..93)
+            .chain(55..68)
+            .map(|seed| seed_to_location.apply(seed))
+            .collect();
+        expected.sort();
+        expected.dedup();
+
+        let distinct = coverage(&locations, i64::MIN..i64::MAX);
+        assert_eq!(distinct, expected.len() as u64);
+        assert_eq!(locations.first().unwrap().start, 46);
+        assert_eq!(locations.last().unwrap().end - 1, *expected.last().unwrap());
+        assert_eq!(
+            coverage(&locations, 0..60),
+            expected.iter().filter(|&&l| l < 60).count() as u64
+        );
+
+        // the lowest location reached is the pt2 answer
+        for (path, _, expected) in FIXTURES {
+            let locations = location_ranges(path).unwrap();
+            assert_eq!(locations[0].start, expected, "{}", path);
+        }
     }
 
     #[test]
-    fn pt2_works() {
-        assert_eq!(pt2("test_input").unwrap(), 46);
-        assert_eq!(pt2("input").unwrap(), 24261545);
-        assert_eq!(pt2("tonys_input").unwrap(), 37806486);
-        assert_eq!(pt2("tims_input").unwrap(), 69841803);
-        assert_eq!(pt2("diggseys_input").unwrap(), 20283860);
+    fn seeds_reaching_works() {
+        let seeds = seeds_reaching("test_input", 47).unwrap();
+        let seed_to_location = category_graph("test_input")
+            .unwrap()
+            .compose("seed", "location")
+            .unwrap();
+
+        // seed 82 is the seed behind the lowest location in pt2
+        assert!(seeds.iter().any(|r| r.contains(&82)));
+        for seed in 0..100 {
+            let reaches = seeds.iter().any(|r| r.contains(&seed));
+            assert_eq!(reaches, seed_to_location.apply(seed) < 47, "seed {}", seed);
+        }
     }
 }